@@ -27,6 +27,15 @@ fn get_integer(text: &mut Feeder) -> Option<(String,u8)> {
     }
 }
 
+fn get_paren(text: &mut Feeder, paren: &str) -> Option<(String,u8)> {
+    if text.starts_with(paren) {
+        let p = text.consume(1);
+        Some( (p.clone(), op_order(&p)) )
+    }else{
+        None
+    }
+}
+
 fn get_operator(text: &mut Feeder) -> Option<(String,u8)> {
     if text.len() == 0 {
         return None;
@@ -40,23 +49,52 @@ fn get_operator(text: &mut Feeder) -> Option<(String,u8)> {
     }
 }
 
-fn reduce(stack: &mut Vec<i32>, op: String ) {
+/* -x is read as -1 * x with an order higher than any binary operator */
+fn get_unary_operator(text: &mut Feeder) -> Option<Vec<(String,u8)>> {
+    if text.starts_with("-") {
+        text.consume(1);
+        Some( vec![("-1".to_string(), 0), ("*".to_string(), 4)] )
+    }else if text.starts_with("+") {
+        text.consume(1);
+        Some( vec![] )
+    }else{
+        None
+    }
+}
+
+fn get_number(token: &str) -> Result<i32, String> {
+    match token.parse::<i32>() {
+        Ok(n) => Ok(n),
+        Err(_) => Err(format!("{}: value too great for base", token)),
+    }
+}
+
+fn reduce(stack: &mut Vec<i32>, op: String ) -> Result<(), String> {
     let op: &str = &op.clone();
 
-    let right = stack.pop().unwrap();
-    let left = stack.pop().unwrap();
+    let (right, left) = match (stack.pop(), stack.pop()) {
+        (Some(r), Some(l)) => (r, l),
+        _ => return Err("syntax error: operand expected".to_string()),
+    };
 
     match op {
-        "+" => stack.push(left+right),
-        "-" => stack.push(left-right),
-        "*" => stack.push(left*right),
-        "/" => stack.push(left/right),
-        _ => (), 
+        "/" | "%" if right == 0 => return Err("division by 0".to_string()),
+        "+" => stack.push(left.wrapping_add(right)),
+        "-" => stack.push(left.wrapping_sub(right)),
+        "*" => stack.push(left.wrapping_mul(right)),
+        "/" => stack.push(left.wrapping_div(right)),
+        "%" => stack.push(left.wrapping_rem(right)),
+        _ => return Err("syntax error in expression".to_string()),
     }
+    Ok(())
 }
 
-pub fn calculate(expression: String, core: &mut ShellCore) -> String {
-    let tokens = tokenizer(expression, core);
+pub fn calculate(expression: String, core: &mut ShellCore) -> Result<String, String> {
+    let tokens = tokenizer(expression, core)?;
+    if tokens.is_empty() {
+        return Ok("0".to_string());
+    }
+
     let mut num_stack: Vec<i32> = vec![];
     let mut wait_stack: Vec<(String,u8)> = vec![];
 
@@ -65,6 +103,11 @@ pub fn calculate(expression: String, core: &mut ShellCore) -> String {
     for t in tokens {
      //   eprintln!("STACK: {:?}", num_stack);
       //  eprintln!("WAIT STACK: {:?}", wait_stack);
+        if t.0 == "(" {
+            wait_stack.push(t);
+            continue;
+        }
+
         while wait_stack.len() != 0 {
             let wtop = wait_stack.pop().unwrap();
 
@@ -75,9 +118,9 @@ pub fn calculate(expression: String, core: &mut ShellCore) -> String {
 
             if wtop.1 <= t.1 {
                 if wtop.1 > 0 {
-                    reduce(&mut num_stack, wtop.0.clone());
+                    reduce(&mut num_stack, wtop.0.clone())?;
                 }else{
-                    num_stack.push(wtop.0.parse::<i32>().unwrap());
+                    num_stack.push(get_number(&wtop.0)?);
                 }
             }else{
                 wait_stack.push(wtop);
@@ -93,10 +136,12 @@ pub fn calculate(expression: String, core: &mut ShellCore) -> String {
 //    eprintln!("-------------------");
     while wait_stack.len() != 0 {
         let wtop = wait_stack.pop().unwrap();
-        if wtop.1 > 0 {
-            reduce(&mut num_stack, wtop.0.clone());
+        if wtop.0 == "(" {
+            return Err("missing `)'".to_string());
+        }else if wtop.1 > 0 {
+            reduce(&mut num_stack, wtop.0.clone())?;
         }else{
-            num_stack.push(wtop.0.parse::<i32>().unwrap());
+            num_stack.push(get_number(&wtop.0)?);
         //    stack.push(wtop);
         }
  //       eprintln!("STACK: {:?}", num_stack);
@@ -104,30 +149,57 @@ pub fn calculate(expression: String, core: &mut ShellCore) -> String {
     }
 
     //stack.iter().map(|t| t.0.clone()).collect::<Vec<String>>().join(" ")
-    num_stack.pop().unwrap().to_string()
+    match (num_stack.pop(), num_stack.len()) {
+        (Some(n), 0) => Ok(n.to_string()),
+        _ => Err("syntax error in expression".to_string()),
+    }
 }
 
-fn tokenizer(expression: String, _core: &mut ShellCore) -> Vec<(String,u8)> {
+fn tokenizer(expression: String, _core: &mut ShellCore) -> Result<Vec<(String,u8)>, String> {
     //let mut stack = vec![];
     let mut tokens = vec![];
     
     let mut text = Feeder::new_from(expression.clone());
     while text.len() != 0 {
         //get value
+        text.consume_blank_return();
+        if text.len() == 0 && tokens.is_empty() {
+            break;
+        }
+
+        loop {
+            if let Some(p) = get_paren(&mut text, "(") {
+                tokens.push(p);
+            }else if let Some(mut u) = get_unary_operator(&mut text) {
+                tokens.append(&mut u);
+            }else{
+                break;
+            }
+            text.consume_blank_return();
+        }
+
         if let Some(n) = get_integer(&mut text) {
             tokens.push(n);
         }else{
-            break;
+            return Err("syntax error: operand expected".to_string());
+        }
+
+        text.consume_blank_return();
+        while let Some(p) = get_paren(&mut text, ")") {
+            tokens.push(p);
+            text.consume_blank_return();
         }
 
         //get operator
-        if let Some(op) = get_operator(&mut text) {
+        if text.len() == 0 {
+            break;
+        }else if let Some(op) = get_operator(&mut text) {
             tokens.push(op);
         }else{
-            break;
+            return Err("syntax error in expression".to_string());
         }
     }
 
-    tokens
+    Ok(tokens)
 }
 
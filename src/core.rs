@@ -11,16 +11,22 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::env;
 use crate::core::shopts::Shopts;
-use nix::sys::wait::{waitpid, WaitStatus, WaitPidFlag};
+use nix::unistd;
 use nix::unistd::Pid;
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use nix::sys::resource::{getrlimit, Resource};
+use nix::sys::termios::{tcgetattr, tcsetattr, SetArg, Termios};
 use crate::core::jobs::Jobs;
+use crate::core::job::{JobStatus, ProcessState};
+use crate::elements::command::function_definition::Function;
+use std::rc::Rc;
 
-use nix::unistd::read;
+use nix::unistd::{close, read};
 use std::os::unix::prelude::RawFd;
 
 pub struct ShellCore {
     pub builtins: HashMap<String, fn(&mut ShellCore, args: &mut Vec<String>) -> i32>,
-    pub functions: HashMap<String, String>,
+    pub functions: HashMap<String, Rc<Function>>,
     pub arrays: HashMap<String, Vec<String>>,
     pub vars: HashMap<String, String>,
     pub args: Vec<String>,
@@ -31,9 +37,37 @@ pub struct ShellCore {
     pub in_double_quot: bool,
     pub pipeline_end: String,
     pub script_file: Option<File>,
+    pub script_name: String,
     pub return_enable: bool,
     pub return_flag: bool,
+    pub abort_flag: bool,
+    pub keep_redirect_flag: bool, // exec without a command keeps the redirections
     pub shopts: Shopts, 
+    pub func_depth: usize,
+    pub proc_sub_fds: Vec<RawFd>,
+    pub coproc_fds: Vec<RawFd>,
+    pub job_pgid: Option<Pid>, // the group that forked processes join. Pid 0: a new group
+    pub job_in_fg: bool,
+    subshell: bool,
+    tty_fd: Option<RawFd>,
+    tty_state: Option<Termios>,
+    stack_base: usize,
+    stack_limit: usize,
+}
+
+/* room left on the stack for a function call and what it parses */
+const STACK_MARGIN: usize = 1024*1024;
+
+fn stack_address() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+fn stack_limit() -> usize {
+    match getrlimit(Resource::RLIMIT_STACK) {
+        Ok((soft, _)) if soft != libc::RLIM_INFINITY => soft as usize,
+        _ => 8*1024*1024,
+    }
 }
 
 impl ShellCore {
@@ -51,9 +85,22 @@ impl ShellCore {
             in_double_quot: false,
             pipeline_end: String::new(),
             script_file: None,
+            script_name: "main".to_string(),
             return_flag: false,
             return_enable: false,
+            abort_flag: false,
+            keep_redirect_flag: false,
             shopts: Shopts::new(),
+            func_depth: 0,
+            proc_sub_fds: vec![],
+            coproc_fds: vec![],
+            job_pgid: None,
+            job_in_fg: false,
+            subshell: false,
+            tty_fd: None,
+            tty_state: None,
+            stack_base: stack_address(),
+            stack_limit: stack_limit(),
         };
 
         conf.set_var("?", &0.to_string());
@@ -107,6 +154,10 @@ impl ShellCore {
             return s.to_string();
         };
 
+        if self.arrays.contains_key(key) {
+            return self.get_array(key, "0");
+        }
+
         if let Ok(s) = env::var(&key) {
             return s.to_string();
         };
@@ -114,14 +165,83 @@ impl ShellCore {
         "".to_string()
     }
 
-    pub fn get_function(&mut self, name: &String) -> Option<String> {
-        if self.functions.contains_key(name) {
-            if let Some(s) = self.functions.get(name) {
-                return Some(s.clone());
+    pub fn get_array(&self, name: &str, index: &str) -> String {
+        let array = match self.arrays.get(name) {
+            Some(a) => a,
+            None    => {
+                /* a scalar behaves as an array with only the 0th element */
+                return match index {
+                    "0" | "@" | "*" => self.get_var(name),
+                    _ => "".to_string(),
+                };
+            },
+        };
+
+        if index == "@" || index == "*" {
+            return array.join(" ");
+        }
+
+        match index.parse::<usize>() {
+            Ok(n) if n < array.len() => array[n].clone(),
+            _ => "".to_string(),
+        }
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<Rc<Function>> {
+        self.functions.get(name).map(Rc::clone)
+    }
+
+    /* FUNCNAME, BASH_SOURCE, and BASH_LINENO grow at their heads
+     * on a function call or source, and "main" sits at the bottom */
+    pub fn push_call_frame(&mut self, name: &str, source: &str, lineno: u32) {
+        if ! self.arrays.contains_key("FUNCNAME") {
+            self.arrays.insert("FUNCNAME".to_string(), vec!["main".to_string()]);
+            self.arrays.insert("BASH_SOURCE".to_string(), vec![self.script_name.clone()]);
+            self.arrays.insert("BASH_LINENO".to_string(), vec!["0".to_string()]);
+        }
+
+        for (key, value) in [("FUNCNAME", name.to_string()),
+                             ("BASH_SOURCE", source.to_string()),
+                             ("BASH_LINENO", lineno.to_string())] {
+            if let Some(a) = self.arrays.get_mut(key) {
+                a.insert(0, value);
             }
         }
+    }
+
+    pub fn pop_call_frame(&mut self) {
+        for key in ["FUNCNAME", "BASH_SOURCE", "BASH_LINENO"] {
+            if let Some(a) = self.arrays.get_mut(key) {
+                a.remove(0);
+                if a.len() <= 1 {
+                    self.arrays.remove(key);
+                }
+            }
+        }
+    }
+
+    /* FUNCNEST, and the native stack even when FUNCNEST is unset */
+    pub fn check_func_nest(&mut self, name: &str) -> bool {
+        let limit = self.get_var("FUNCNEST").parse::<usize>().unwrap_or(0);
+        if limit > 0 && self.func_depth >= limit {
+            eprintln!("bash: {}: maximum function nesting level exceeded ({})", name, limit);
+        }else if self.stack_base.saturating_sub(stack_address()) + STACK_MARGIN > self.stack_limit {
+            eprintln!("bash: {}: maximum function nesting level exceeded (stack size)", name);
+        }else{
+            return true;
+        }
 
-        None
+        self.set_var("?", "1");
+        self.abort_flag = true;
+        self.return_flag = true;
+        false
+    }
+
+    pub fn current_source(&self) -> String {
+        match self.arrays.get("BASH_SOURCE") {
+            Some(a) => a[0].clone(),
+            None    => self.script_name.clone(),
+        }
     }
 
     pub fn get_builtin(&self, name: &String) 
@@ -144,29 +264,17 @@ impl ShellCore {
         let mut ans = "".to_string();
         let mut ch = [0;1000];
     
-        loop {
-            while let Ok(n) = read(pin, &mut ch) {
-                ans += &String::from_utf8(ch[..n].to_vec()).unwrap();
-                match waitpid(pid, Some(WaitPidFlag::WNOHANG)).expect("Faild to wait child process.") {
-                    WaitStatus::StillAlive => {
-                        continue;
-                    },
-                    WaitStatus::Exited(_pid, status) => {
-                        self.set_var("?", &status.to_string());
-                        break;
-                    },
-                    WaitStatus::Signaled(pid, signal, _) => {
-                        self.set_var("?", &(128+signal as i32).to_string());
-                        eprintln!("Pid: {:?}, Signal: {:?}", pid, signal);
-                        break;
-                    },
-                    _ => {
-                        break;
-                    },
-                };
+        while let Ok(n) = read(pin, &mut ch) {
+            if n == 0 {
+                break;
             }
-            return ans;
+            ans += &String::from_utf8(ch[..n].to_vec()).unwrap();
         }
+
+        let (state, _, _) = proc::wait_process(pid);
+        proc::report_signal(pid, state);
+        self.set_var("?", &state.exit_status().to_string());
+        ans
     }
 
     pub fn wait_job(&mut self) { //only for fg job
@@ -183,7 +291,93 @@ impl ShellCore {
             .join(" ");
 
         self.set_var("PIPESTATUS", &s);
-        self.jobs.foreground.status = 'D';
+        self.jobs.foreground.status = JobStatus::Done;
+
+        if self.jobs.foreground.pgid.is_some() {
+            let states = self.jobs.foreground.states.clone();
+            self.reclaim_terminal(&states);
+        }
+
+        if self.has_flag('b') {
+            self.check_jobs();
+        }
+    }
+
+    /* keeps the terminal and its settings for job control */
+    pub fn init_tty(&mut self) {
+        if ! unistd::isatty(0).unwrap_or(false) {
+            return;
+        }
+
+        if let Ok(fd) = fcntl(0, FcntlArg::F_DUPFD_CLOEXEC(255)) {
+            self.tty_fd = Some(fd);
+            self.tty_state = tcgetattr(fd).ok();
+        }
+    }
+
+    pub fn give_terminal_to(&self, pgid: Pid) {
+        if let Some(fd) = self.tty_fd {
+            let _ = unistd::tcsetpgrp(fd, pgid);
+        }
+    }
+
+    /* takes the terminal back from a foreground job. The settings changed by the job
+     * are kept only when it has exited normally, as bash does. */
+    pub fn reclaim_terminal(&mut self, states: &[ProcessState]) {
+        let fd = match self.tty_fd {
+            Some(fd) => fd,
+            None => return,
+        };
+        let _ = unistd::tcsetpgrp(fd, unistd::getpgrp());
+
+        let exited = states.iter().all(|s| matches!(s, ProcessState::Exited(_)));
+        if exited {
+            self.tty_state = tcgetattr(fd).ok();
+        }else if let Some(t) = &self.tty_state {
+            let _ = tcsetattr(fd, SetArg::TCSADRAIN, t);
+        }
+    }
+
+    /* called first in a forked process of a job: joins the process group of the job,
+     * and takes the terminal if the job is in the foreground */
+    pub fn join_job_group(&mut self) {
+        if let Some(pgid) = self.job_pgid {
+            let _ = unistd::setpgid(Pid::from_raw(0), pgid);
+            if self.job_in_fg {
+                self.give_terminal_to(unistd::getpgrp());
+            }
+        }
+        self.stop_job_control();
+    }
+
+    /* subshells don't control jobs even if monitor is on */
+    pub fn stop_job_control(&mut self) {
+        self.job_pgid = None;
+        self.subshell = true;
+    }
+
+    pub fn job_control(&self) -> bool {
+        self.has_flag('m') && ! self.subshell
+    }
+
+    /* closes the pipe ends of process substitutions opened after the first n */
+    pub fn close_proc_subs(&mut self, n: usize) {
+        while self.proc_sub_fds.len() > n {
+            let fd = self.proc_sub_fds.pop().unwrap();
+            let _ = close(fd);
+        }
+    }
+
+    /* The pipe ends of coprocesses are not available in subshells. An fd without
+     * FD_CLOEXEC has been closed and reused after the coprocess started. */
+    pub fn close_coproc_fds(&mut self) {
+        for fd in self.coproc_fds.drain(..) {
+            if let Ok(flags) = fcntl(fd, FcntlArg::F_GETFD) {
+                if FdFlag::from_bits_truncate(flags).contains(FdFlag::FD_CLOEXEC) {
+                    let _ = close(fd);
+                }
+            }
+        }
     }
 
     pub fn reverse_exit_status(&mut self) {
@@ -192,18 +386,18 @@ impl ShellCore {
     }
 
     pub fn check_jobs(&mut self) {
+        self.jobs.proc_sub_pids.retain(|p| ! proc::take_state(*p).is_some_and(|s| s.is_finished()));
+
         for j in 1..self.jobs.backgrounds.len() {
-            if self.jobs.backgrounds[j].async_pids.len() != 0 {
-                self.jobs.backgrounds[j].check_of_finish();
-            }
+            self.jobs.backgrounds[j].check_of_finish();
         }
 
         let (first, second) = self.jobs.get_top_priority_id();
 
         //let mut minus_to_plus = false;
         for j in self.jobs.backgrounds.iter_mut() {
-            if j.status == 'D' { //done
-                j.print_status(first, second);
+            if j.status == JobStatus::Done {
+                j.print_status(first, second, false);
                 /*
                 if self.jobs.backgrounds[j].mark == '+' {
                     minus_to_plus = true;
@@ -2,12 +2,14 @@
 //SPDX-License-Identifier: BSD-3-Clause
 
 use std::process;
-use std::{io,fs,env};
+use std::{fs,env};
 use std::path::Path;
 use std::fs::OpenOptions;
 use std::io::{Write, BufReader, BufRead};
 use crate::bash_glob::glob_match;
-use super::job::Job;
+use super::job::{Job, JobStatus};
+use super::proc;
+use std::convert::TryFrom;
 use nix::sys::signal;
 use nix::sys::signal::Signal;
 use nix::unistd;
@@ -15,21 +17,26 @@ use nix::unistd;
 use crate::Script;
 use crate::ShellCore;
 use crate::Feeder;
+use std::ffi::CString;
 
 pub fn set_builtins(core: &mut ShellCore){
     core.builtins.insert(".".to_string(), source);
     core.builtins.insert(":".to_string(), true_);
     core.builtins.insert("alias".to_string(), alias);
     core.builtins.insert("builtin".to_string(), builtin);
+    core.builtins.insert("caller".to_string(), caller);
     core.builtins.insert("bg".to_string(), bg);
     core.builtins.insert("cd".to_string(), cd);
+    core.builtins.insert("disown".to_string(), disown);
     core.builtins.insert("eval".to_string(), eval);
+    core.builtins.insert("exec".to_string(), exec);
     core.builtins.insert("exit".to_string(), exit);
     core.builtins.insert("export".to_string(), export);
     core.builtins.insert("false".to_string(), false_);
     core.builtins.insert("fg".to_string(), fg);
     core.builtins.insert("history".to_string(), history);
     core.builtins.insert("jobs".to_string(), jobs);
+    core.builtins.insert("kill".to_string(), kill);
     core.builtins.insert("pwd".to_string(), pwd);
     core.builtins.insert("set".to_string(), set);
     core.builtins.insert("shift".to_string(), shift);
@@ -101,80 +108,134 @@ pub fn false_(_core: &mut ShellCore, _args: &mut Vec<String>) -> i32 {
     1
 }
 
+/* the jobs given as job specs or pids, or the current job */
+fn job_args(core: &ShellCore, args: &[String], builtin: &str) -> Vec<Result<usize, ()>> {
+    let specs = if args.is_empty() {vec!["%+".to_string()]}else{args.to_vec()};
+    specs.iter().map(|spec| match spec.parse::<i32>() {
+        Ok(n) => core.jobs.find_job_by_pid(unistd::Pid::from_raw(n))
+                     .ok_or(format!("{}: no such job", spec)),
+        Err(_) => core.jobs.find_job(spec),
+    }).map(|pos| match pos {
+        Ok(pos) => Ok(pos),
+        Err(e)  => {
+            eprintln!("bash: {}: {}", builtin, e);
+            Err(())
+        },
+    }).collect()
+}
+
 pub fn bg(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     let (first, second) = core.jobs.get_top_priority_id();
 
     fn bg_core (job: &mut Job, first: usize, second: usize) {
-        job.status = 'R';
+        job.continue_job();
         println!("{}", &job.status_string(first, second));
-        for p in &job.async_pids {
-            signal::kill(*p, Signal::SIGCONT).unwrap();
-        }
     }
 
-    if args.len() < 2 {
-        for j in 0..core.jobs.backgrounds.len() {
-            if core.jobs.backgrounds[j].id == first {
-                bg_core(&mut core.jobs.backgrounds[j], first, second);
-            }
+    let mut ans = 0;
+    for pos in job_args(core, &args[1..], "bg") {
+        let job = match pos {
+            Ok(p) => &mut core.jobs.backgrounds[p],
+            Err(_) => {
+                ans = 1;
+                continue;
+            },
+        };
+
+        job.check_of_finish();
+        if job.status == JobStatus::Done {
+            eprintln!("bash: bg: job has terminated");
+            ans = 1;
+        }else if job.status == JobStatus::Running {
+            eprintln!("bash: bg: job {} already in background", job.id);
+        }else{
+            bg_core(job, first, second);
         }
-        return 0;
     }
+    ans
+}
 
-    args[1] = args[1].trim_start_matches("%").to_string();
-    let job_pos = if let Ok(n) = args[1].parse::<usize>() {
-        n - 1
-    }else{
-        eprintln!("bash: bg: {}: no such job", args[1]);
-        return 1;
+pub fn fg(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let j = match job_args(core, &args[1..2.min(args.len())], "fg")[0] {
+        Ok(p) => p,
+        Err(_) => return 1,
     };
 
-    let status = core.jobs.backgrounds[job_pos].status;
-    let id = core.jobs.backgrounds[job_pos].id;
-    if job_pos >= core.jobs.backgrounds.len() || status == 'D' || status == 'I' {
-        eprintln!("bash: bg: {}: no such job", id);
+    let job = &mut core.jobs.backgrounds[j];
+    job.check_of_finish();
+    if job.status != JobStatus::Stopped && job.status != JobStatus::Running {
+        eprintln!("bash: fg: job has terminated");
         return 1;
-    }else if status == 'R' {
-        eprintln!("bash: bg: job {} already in background", id);
-        return 0;
     }
 
-    bg_core(&mut core.jobs.backgrounds[job_pos], first, second);
-    0
-}
+    let pgid = job.pgid;
+    if let Some(pg) = pgid {
+        core.give_terminal_to(pg);
+    }
+    let job = &mut core.jobs.backgrounds[j];
+    let text = job.text.trim();
+    println!("{}", text.strip_suffix('&').unwrap_or(text).trim_end());
+    job.continue_job();
+    job.status = JobStatus::Foreground;
+    core.jobs.foreground = job.clone();
+    let pipestatus = core.jobs.wait_bg_job_at_foreground(core.jobs.backgrounds[j].id);
 
-pub fn fg(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    let (first, _second) = core.jobs.get_top_priority_id();
+    if pgid.is_some() {
+        let states = core.jobs.backgrounds[j].states.clone();
+        core.reclaim_terminal(&states);
+    }
 
-    if args.len() < 2 {
-        for j in 0..core.jobs.backgrounds.len() {
-            if core.jobs.backgrounds[j].status != 'S' && core.jobs.backgrounds[j].status != 'R' {
-                continue;
-            }
+    let job = &mut core.jobs.backgrounds[j];
+    if job.status == JobStatus::Done { // a foreground job is not reported
+        job.status = JobStatus::Invalid;
+    }
+    *pipestatus.last().unwrap_or(&0)
+}
 
-            if core.jobs.backgrounds[j].id != first {
-                continue;
+/* disown [-ar] [-h] [jobspec ...]
+ * -h keeps the job in the table; the shell sends no SIGHUP to jobs anyway. */
+pub fn disown(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let (mut all, mut running, mut keep) = (false, false, false);
+    let mut i = 1;
+    while i < args.len() && args[i].starts_with('-') && args[i].len() > 1 {
+        for c in args[i][1..].chars() {
+            match c {
+                'a' => all = true,
+                'r' => running = true,
+                'h' => keep = true,
+                _ => {
+                    eprintln!("bash: disown: -{}: invalid option", c);
+                    eprintln!("disown: usage: disown [-h] [-ar] [jobspec ... | pid ...]");
+                    return 2;
+                },
             }
+        }
+        i += 1;
+    }
 
-            core.jobs.backgrounds[j].status = 'F';
-            for p in &core.jobs.backgrounds[j].async_pids {
-                if ! core.jobs.backgrounds[j].signaled_bg {
-                    unistd::tcsetpgrp(0, p.clone()).expect("Bash internal error (tcsetpgrp)");
-                    unistd::tcsetpgrp(1, p.clone()).expect("Bash internal error (tcsetpgrp)");
+    let mut ans = 0;
+    let positions: Vec<usize> = if (all || running) && i == args.len() {
+        (0..core.jobs.backgrounds.len())
+            .filter(|p| core.jobs.backgrounds[*p].status != JobStatus::Invalid)
+            .filter(|p| ! running || core.jobs.backgrounds[*p].status == JobStatus::Running)
+            .collect()
+    }else{
+        job_args(core, &args[i..], "disown").into_iter()
+            .filter_map(|p| {
+                if p.is_err() {
+                    ans = 1;
                 }
-                signal::kill(*p, Signal::SIGCONT).unwrap();
-            }
-            core.jobs.foreground = core.jobs.backgrounds[j].clone();
-            core.jobs.wait_bg_job_at_foreground(core.jobs.backgrounds[j].id);
+                p.ok()
+            }).collect()
+    };
 
-            if ! core.jobs.backgrounds[j].signaled_bg {
-                unistd::tcsetpgrp(0, unistd::getpid()).expect("Bash internal error (tcsetpgrp)");
-                unistd::tcsetpgrp(1, unistd::getpid()).expect("Bash internal error (tcsetpgrp)");
-            }
+    if ! keep {
+        for p in positions {
+            core.jobs.backgrounds[p].status = JobStatus::Invalid;
         }
-        return 0;
+        core.jobs.remove_finished_jobs();
     }
-    0
+    ans
 }
 
 pub fn shift(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
@@ -277,6 +338,28 @@ pub fn alias(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     0
 }
 
+/* the long names of the flags for set -o */
+const SET_OPTIONS: [(&str, char); 5] = [
+    ("monitor", 'm'),
+    ("noclobber", 'C'),
+    ("notify", 'b'),
+    ("verbose", 'v'),
+    ("xtrace", 'x'),
+];
+
+fn set_flag(core: &mut ShellCore, flag: char, on: bool) -> bool {
+    if ! SET_OPTIONS.iter().any(|o| o.1 == flag) {
+        return false;
+    }
+
+    core.flags.retain(|c| c != flag);
+    if on {
+        core.flags.push(flag);
+    }
+    true
+}
+
+/* set [-Cvx] [-o option] [--] [arg ...] */
 pub fn set(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if args.len() == 1 {
        for k in core.vars.keys() {
@@ -285,21 +368,82 @@ pub fn set(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
        return 0;
     }
 
-    core.args.clear();
+    let mut i = 1;
+    let mut positional = false;
+    while i < args.len() {
+        let arg = args[i].clone();
+        if arg == "--" {
+            positional = true;
+            i += 1;
+            break;
+        }
+        if arg.len() < 2 || ! (arg.starts_with('-') || arg.starts_with('+')) {
+            break;
+        }
+
+        let on = arg.starts_with('-');
+        if arg == "-o" || arg == "+o" {
+            i += 1;
+            if i >= args.len() {
+                for (name, flag) in SET_OPTIONS {
+                    let onoff = if core.has_flag(flag) {"on"}else{"off"};
+                    println!("{:<15}\t{}", name, onoff);
+                }
+                return 0;
+            }
+            match SET_OPTIONS.iter().find(|o| o.0 == args[i]) {
+                Some((_, flag)) => { set_flag(core, *flag, on); },
+                None => {
+                    eprintln!("bash: set: {}: invalid option name", args[i]);
+                    return 1;
+                },
+            }
+        }else{
+            for c in arg[1..].chars() {
+                if ! set_flag(core, c, on) {
+                    eprintln!("bash: set: {}{}: invalid option", &arg[..1], c);
+                    return 2;
+                }
+            }
+        }
+        i += 1;
+    }
 
-    for a in args {
-        core.args.push(a.to_string());
+    if positional || i < args.len() {
+        core.args.truncate(1);
+        for a in &args[i..] {
+            core.args.push(a.to_string());
+        }
     }
 
     0
 }
 
-pub fn read(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
-    let mut line = String::new();
-    if io::stdin().read_line(&mut line).expect("Failed to read line") == 0 {
-        return 1;
+/* reads fd 0 byte by byte so that nothing after the line is taken
+ * from a file or pipe shared with other processes */
+fn read_line_fd0() -> Option<String> {
+    let mut line = vec![];
+    let mut ch = [0; 1];
+    while let Ok(1) = unistd::read(0, &mut ch) {
+        line.push(ch[0]);
+        if ch[0] == b'\n' {
+            break;
+        }
     }
 
+    if line.is_empty() {
+        None
+    }else{
+        Some(String::from_utf8_lossy(&line).to_string())
+    }
+}
+
+pub fn read(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let line = match read_line_fd0() {
+        Some(l) => l,
+        None    => return 1,
+    };
+
     let wordnum = args.len() - 1;
     if wordnum < 1 {
         return 0;
@@ -345,9 +489,15 @@ pub fn source(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
             Ok(source) => {
                 let mut feeder = Feeder::new_from(source);
                 if let Some(mut script) = Script::parse(&mut feeder, core) {
+                    let lineno = core.get_var("LINENO").parse::<u32>().unwrap_or(0);
+                    core.push_call_frame("source", &args[1], lineno);
+                    let return_enable = core.return_enable;
                     core.return_enable = true;
                     script.exec(core);
-                    core.return_enable = false;
+                    core.return_enable = return_enable;
+                    core.return_flag = core.abort_flag;
+                    core.pop_call_frame();
+                    return core.get_var("?").parse::<i32>().unwrap_or(1);
                 }else{
                     return 1;
                 };
@@ -358,30 +508,235 @@ pub fn source(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     0
 }
 
-pub fn return_(core: &mut ShellCore, _args: &mut Vec<String>) -> i32 {
+pub fn return_(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if core.return_enable {
         core.return_flag = true;
-        0
+        if args.len() < 2 {
+            return core.get_var("?").parse::<i32>().unwrap_or(0);
+        }
+
+        match args[1].parse::<i32>() {
+            Ok(n) => n & 0xFF,
+            Err(_) => {
+                eprintln!("bash: return: {}: numeric argument required", args[1]);
+                2
+            },
+        }
     }else{
         eprintln!("Builtin return is only enabled in a function or source");
         1
     }
 }
 
-pub fn jobs(core: &mut ShellCore, _args: &mut Vec<String>) -> i32 {
+/* caller [expr]: "lineno source" of the current call, or
+ * "lineno subroutine source" of the expr-th frame */
+pub fn caller(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let frame = |key: &str, n: usize| {
+        core.arrays.get(key).and_then(|a| a.get(n)).cloned()
+    };
+
+    if args.len() < 2 {
+        return match (frame("BASH_LINENO", 0), frame("BASH_SOURCE", 1)) {
+            (Some(lineno), Some(source)) => {
+                println!("{} {}", lineno, source);
+                0
+            },
+            _ => 1,
+        };
+    }
+
+    let n = match args[1].parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => {
+            eprintln!("bash: caller: {}: invalid number", args[1]);
+            return 2;
+        },
+    };
+
+    match (frame("BASH_LINENO", n), frame("FUNCNAME", n+1), frame("BASH_SOURCE", n+1)) {
+        (Some(lineno), Some(name), Some(source)) => {
+            println!("{} {} {}", lineno, name, source);
+            0
+        },
+        _ => 1,
+    }
+}
+
+/* jobs [-lprs] [jobspec ...] */
+pub fn jobs(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let (mut with_pid, mut pid_only, mut running, mut stopped) = (false, false, false, false);
+    let mut i = 1;
+    while i < args.len() && args[i].starts_with('-') && args[i].len() > 1 {
+        for c in args[i][1..].chars() {
+            match c {
+                'l' => with_pid = true,
+                'p' => pid_only = true,
+                'r' => running = true,
+                's' => stopped = true,
+                _ => {
+                    eprintln!("bash: jobs: -{}: invalid option", c);
+                    eprintln!("jobs: usage: jobs [-lprs] [jobspec ...]");
+                    return 2;
+                },
+            }
+        }
+        i += 1;
+    }
+
     let (first, second) = core.jobs.get_top_priority_id();
 
     for j in core.jobs.backgrounds.iter_mut() {
-        if j.async_pids.len() != 0 {
-            j.check_of_finish();
+        j.check_of_finish();
+    }
+
+    let mut ans = 0;
+    let positions: Vec<usize> = if i == args.len() {
+        (0..core.jobs.backgrounds.len()).collect()
+    }else{
+        job_args(core, &args[i..], "jobs").into_iter()
+            .filter_map(|p| {
+                if p.is_err() {
+                    ans = 1;
+                }
+                p.ok()
+            }).collect()
+    };
+
+    for p in positions {
+        let job = &mut core.jobs.backgrounds[p];
+        if job.status == JobStatus::Invalid
+        || (running && job.status != JobStatus::Running)
+        || (stopped && job.status != JobStatus::Stopped) {
+            continue;
+        }
+
+        if pid_only {
+            if let Some(pid) = job.leader_pid() {
+                println!("{}", pid);
+            }
+        }else{
+            job.print_status(first, second, with_pid);
         }
     }
+    ans
+}
 
-    for j in core.jobs.backgrounds.iter_mut() {
-        j.print_status(first, second);
+/* the whole process group when the job has its own one */
+fn kill_job(job: &Job, sig: Option<Signal>) -> nix::Result<()> {
+    match job.pgid {
+        Some(pgid) => signal::killpg(pgid, sig),
+        None => job.pids.iter().enumerate()
+                   .filter(|(i, _)| ! job.states[*i].is_finished())
+                   .try_for_each(|(_, p)| signal::kill(*p, sig)),
     }
+}
 
-    0
+fn kill_list(args: &[String]) -> i32 {
+    if args.is_empty() {
+        for (i, (num, name)) in proc::signal_list().iter().enumerate() {
+            print!("{:2}) SIG{:<10}", num, name);
+            if i%5 == 4 {
+                println!();
+            }
+        }
+        println!();
+        return 0;
+    }
+
+    let mut ans = 0;
+    for a in args {
+        match a.parse::<i32>() {
+            Ok(n) => match proc::signal_name(if n > 128 {n - 128}else{n}) {
+                Some(name) => println!("{}", name),
+                None => {
+                    eprintln!("bash: kill: {}: invalid signal specification", a);
+                    ans = 1;
+                },
+            },
+            Err(_) => match proc::signal_number(a) {
+                Some(n) => println!("{}", n),
+                None => {
+                    eprintln!("bash: kill: {}: invalid signal specification", a);
+                    ans = 1;
+                },
+            },
+        }
+    }
+    ans
+}
+
+/* kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec] */
+pub fn kill(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let usage = "kill: usage: kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]";
+    if args.len() < 2 {
+        eprintln!("{}", usage);
+        return 2;
+    }
+
+    if args[1] == "-l" || args[1] == "-L" {
+        return kill_list(&args[2..]);
+    }
+
+    let mut sig = libc::SIGTERM;
+    let mut i = 1;
+    if args[1] == "-s" || args[1] == "-n" {
+        if args.len() < 3 {
+            eprintln!("bash: kill: {}: option requires an argument", args[1]);
+            eprintln!("{}", usage);
+            return 2;
+        }
+        sig = match proc::signal_number(&args[2]) {
+            Some(n) => n,
+            None => {
+                eprintln!("bash: kill: {}: invalid signal specification", args[2]);
+                return 1;
+            },
+        };
+        i = 3;
+    }else if args[1] == "--" {
+        i = 2;
+    }else if args[1].starts_with('-') {
+        sig = match proc::signal_number(&args[1][1..]) {
+            Some(n) => n,
+            None => {
+                eprintln!("bash: kill: {}: invalid signal specification", &args[1][1..]);
+                return 1;
+            },
+        };
+        i = 2;
+    }
+
+    if i >= args.len() {
+        eprintln!("{}", usage);
+        return 2;
+    }
+
+    let signal = if sig == 0 {None}else{Signal::try_from(sig).ok()};
+    let mut ans = 0;
+    for a in &args[i..] {
+        let result = if a.starts_with('%') {
+            match core.jobs.find_job(a) {
+                Ok(pos) => kill_job(&core.jobs.backgrounds[pos], signal),
+                Err(e) => {
+                    eprintln!("bash: kill: {}", e);
+                    ans = 1;
+                    continue;
+                },
+            }
+        }else if let Ok(n) = a.parse::<i32>() {
+            signal::kill(unistd::Pid::from_raw(n), signal)
+        }else{
+            eprintln!("bash: kill: {}: arguments must be process or job IDs", a);
+            ans = 1;
+            continue;
+        };
+
+        if let Err(e) = result {
+            eprintln!("bash: kill: ({}) - {}", a, e.desc());
+            ans = 1;
+        }
+    }
+    ans
 }
 
 pub fn shopt(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
@@ -458,6 +813,76 @@ pub fn eval(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     core.get_var("?").parse::<i32>().unwrap()
 }
 
+/* exec [-cl] [-a name] [command [arguments]]
+ * Without a command, the caller keeps the redirections in the shell. */
+pub fn exec(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
+    let mut clear_env = false;
+    let mut login = false;
+    let mut name = None;
+
+    let mut i = 1;
+    while i < args.len() && args[i].starts_with('-') && args[i] != "-" {
+        let opt = args[i].clone();
+        i += 1;
+        if opt == "--" {
+            break;
+        }
+
+        for c in opt[1..].chars() {
+            match c {
+                'c' => clear_env = true,
+                'l' => login = true,
+                'a' if i < args.len() => {
+                    name = Some(args[i].clone());
+                    i += 1;
+                },
+                'a' => {
+                    eprintln!("bash: exec: -a: option requires an argument");
+                    return 2;
+                },
+                _ => {
+                    eprintln!("bash: exec: -{}: invalid option", c);
+                    eprintln!("exec: usage: exec [-cl] [-a name] [command [argument ...]]");
+                    return 2;
+                },
+            }
+        }
+    }
+
+    if i >= args.len() {
+        core.keep_redirect_flag = true;
+        return 0;
+    }
+
+    let mut argv0 = name.unwrap_or(args[i].clone());
+    if login {
+        argv0 = "-".to_owned() + &argv0;
+    }
+
+    let file = CString::new(args[i].clone()).unwrap();
+    let cargs: Vec<CString> = [argv0].iter().chain(args[i+1..].iter())
+        .map(|a| CString::new(a.to_string()).unwrap())
+        .collect();
+    let envs: Vec<CString> = if clear_env {
+        vec![]
+    }else{
+        env::vars().map(|v| CString::new(format!("{}={}", v.0, v.1)).unwrap()).collect()
+    };
+
+    let _ = std::io::stdout().flush();
+    proc::set_signals();
+    proc::unset_sigchld_handler();
+    let _ = unistd::execvpe(&file, &cargs, &envs);
+    proc::ignore_signals();
+    proc::set_sigchld_handler();
+
+    eprintln!("bash: exec: {}: not found", args[i]);
+    if ! core.has_flag('i') {
+        process::exit(127);
+    }
+    127
+}
+
 pub fn glob_test(_core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     if glob_match(&args[1].to_string(), &args[2].to_string()){
         eprintln!("MATCH!");
@@ -468,20 +893,67 @@ pub fn glob_test(_core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     }
 }
 
-pub fn wait(core: &mut ShellCore, _args: &mut Vec<String>) -> i32 {
+fn wait_job(core: &mut ShellCore, pos: usize, first: usize, second: usize) -> i32 {
+    let job = &mut core.jobs.backgrounds[pos];
+    if job.status == JobStatus::Done {
+        return *job.pipestatus().last().unwrap_or(&0);
+    }
+    if job.status != JobStatus::Running && job.status != JobStatus::Foreground { 
+        return 0;
+    }
+    job.status = JobStatus::Foreground;
+    let id = job.id;
+    let pipestatus = core.jobs.wait_bg_job_at_foreground(id);
+    let job = &mut core.jobs.backgrounds[pos];
+    if job.status == JobStatus::Done {
+        eprintln!("{}", &job.status_string(first, second));
+        job.status = JobStatus::Invalid;
+    }
+    *pipestatus.last().unwrap_or(&0)
+}
+
+/* wait [id ...]: id is a jobspec or a pid */
+pub fn wait(core: &mut ShellCore, args: &mut Vec<String>) -> i32 {
     let (first, second) = core.jobs.get_top_priority_id();
 
-    for i in 0..core.jobs.backgrounds.len() {
-        if core.jobs.backgrounds[i].status != 'R' && core.jobs.backgrounds[i].status != 'F' { 
-            continue;
+    if args.len() < 2 {
+        for i in 0..core.jobs.backgrounds.len() {
+            wait_job(core, i, first, second);
         }
-        core.jobs.backgrounds[i].status = 'F';
-        let id = core.jobs.backgrounds[i].id;
-        core.jobs.wait_bg_job_at_foreground(id);
-        core.jobs.backgrounds[i].status = 'D';
-        eprintln!("{}", &core.jobs.backgrounds[i].status_string(first, second));
-        core.jobs.backgrounds[i].status = 'I';
+        core.jobs.wait_proc_subs();
+        return 0;
     }
 
-    0
+    let mut ans = 0;
+    for a in &args[1..] {
+        if a.starts_with('%') {
+            ans = match core.jobs.find_job(a) {
+                Ok(pos) => wait_job(core, pos, first, second),
+                Err(e)  => {
+                    eprintln!("bash: wait: {}", e);
+                    127
+                },
+            };
+            continue;
+        }
+
+        let pid = match a.parse::<i32>() {
+            Ok(n) => unistd::Pid::from_raw(n),
+            Err(_) => {
+                eprintln!("bash: wait: `{}': not a pid or valid job spec", a);
+                ans = 2;
+                continue;
+            },
+        };
+
+        ans = if let Some(p) = core.jobs.find_job_by_pid(pid) {
+            wait_job(core, p, first, second)
+        }else if let Some(status) = core.jobs.wait_proc_sub(pid) {
+            status
+        }else{
+            eprintln!("bash: wait: pid {} is not a child of this shell", a);
+            127
+        };
+    }
+    ans
 }
@@ -2,19 +2,68 @@
 //SPDX-License-Identifier: BSD-3-Clause
 
 use nix::unistd::Pid;
+use nix::sys::signal;
+use nix::sys::signal::Signal;
+use nix::sys::wait::WaitStatus;
 use crate::elements::command::Command;
 use super::proc;
+use std::time::Duration;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum ProcessState {
+    Running,
+    Stopped(Signal),
+    Exited(i32),
+    Signaled(Signal),
+}
+
+impl ProcessState {
+    /* None for StillAlive and the statuses of ptrace */
+    pub fn from_wait_status(ws: WaitStatus) -> Option<ProcessState> {
+        match ws {
+            WaitStatus::Exited(_, status)     => Some(ProcessState::Exited(status)),
+            WaitStatus::Signaled(_, sig, _)   => Some(ProcessState::Signaled(sig)),
+            WaitStatus::Stopped(_, sig)       => Some(ProcessState::Stopped(sig)),
+            WaitStatus::Continued(_)          => Some(ProcessState::Running),
+            _                                 => None,
+        }
+    }
+
+    pub fn exit_status(&self) -> i32 {
+        match self {
+            ProcessState::Exited(status)  => *status,
+            ProcessState::Signaled(sig)
+            | ProcessState::Stopped(sig)  => 128 + *sig as i32,
+            ProcessState::Running         => 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ProcessState::Exited(_) | ProcessState::Signaled(_))
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum JobStatus {
+    Foreground, // waited by the shell
+    Running,
+    Stopped,
+    Done,       // finished and not reported yet
+    Invalid,    // reported, and removed from the table soon
+}
 
 //[1]+  Running                 sleep 5 &
 #[derive(Clone,Debug)]
 pub struct Job {
-    pub signaled_bg: bool,
     pub pids: Vec<Pid>,
-    pub async_pids: Vec<Pid>, //maybe not required.
+    pub states: Vec<ProcessState>, // one for each of pids
+    pub pgid: Option<Pid>, // None when the job is in the group of the shell
     pub text: String,
-    pub status: char, // S: stopped, R: running, D: done, I: invalid, F: fg
+    pub status: JobStatus,
     pub id: usize,
     pub priority: u32,
+    pub user_time: Duration,
+    pub sys_time: Duration,
 }
 
 impl Job {
@@ -27,37 +76,79 @@ impl Job {
         }
 
         Job {
-            signaled_bg: false,
-            pids: pids,
-            async_pids: vec![],
+            states: vec![ProcessState::Running; pids.len()],
+            pgid: if is_bg {pids.first().copied()}else{None},
+            pids,
             text: text.clone(),
-            status: if is_bg {'R'}else{'F'},
+            status: if is_bg {JobStatus::Running}else{JobStatus::Foreground},
             id: 0,
-            priority: 0, 
+            priority: 0,
+            user_time: Duration::ZERO,
+            sys_time: Duration::ZERO,
         }
     }
 
-    pub fn check_of_finish(&mut self) -> bool {
-        if self.status != 'R' {
-            return true; 
+    /* Running, Stopped, or Done from the states of the processes */
+    pub fn update_status(&mut self) {
+        if self.status != JobStatus::Running && self.status != JobStatus::Stopped {
+            return;
         }
 
-        let mut remain = vec![];
+        self.status = if self.states.iter().all(|s| s.is_finished()) {
+            JobStatus::Done
+        }else if self.states.iter().any(|s| matches!(s, ProcessState::Stopped(_))) {
+            JobStatus::Stopped
+        }else{
+            JobStatus::Running
+        };
+    }
 
-        while self.async_pids.len() > 0 {
-            let p = self.async_pids.pop().unwrap();
-            if ! proc::check_async_process(p){
-                remain.push(p);
-            }
+    pub fn is_stopped(&self) -> bool {
+        self.states.iter().any(|s| matches!(s, ProcessState::Stopped(_)))
+    }
+
+    pub fn pipestatus(&self) -> Vec<i32> {
+        self.states.iter().map(|s| s.exit_status()).collect()
+    }
+
+    /* true if finished */
+    pub fn check_of_finish(&mut self) -> bool {
+        if self.status != JobStatus::Running && self.status != JobStatus::Stopped {
+            return true;
         }
 
-        if remain.len() == 0 {
-            self.status = 'D';
+        for i in 0..self.pids.len() {
+            if self.states[i].is_finished() {
+                continue;
+            }
+            if let Some(state) = proc::take_state(self.pids[i]) {
+                self.states[i] = state;
+            }
         }
 
-        self.async_pids = remain;
+        self.update_status();
+        self.status == JobStatus::Done
+    }
 
-        self.async_pids.len() == 0 // true if finished
+    /* sends SIGCONT to the whole job */
+    pub fn continue_job(&mut self) {
+        match self.pgid {
+            Some(pgid) => { let _ = signal::killpg(pgid, Signal::SIGCONT); },
+            None => {
+                for (i, p) in self.pids.iter().enumerate() {
+                    if ! self.states[i].is_finished() {
+                        let _ = signal::kill(*p, Signal::SIGCONT);
+                    }
+                }
+            },
+        }
+
+        for s in self.states.iter_mut() {
+            if let ProcessState::Stopped(_) = s {
+                *s = ProcessState::Running;
+            }
+        }
+        self.status = JobStatus::Running;
     }
 
     pub fn status_string(&self, first: usize, second: usize) -> String {
@@ -69,35 +160,53 @@ impl Job {
             ' '
         };
 
-        let status = match self.status {
-            'D' => "Done",
-            'S' => "Stopped",
-            'R' => "Running",
-            _   => "ERROR",
-        };
+        format!("[{}]{} {}\t\t{}", &self.id, mark, self.status_name(), &self.text.trim())
+    }
 
-        format!("[{}]{} {}\t\t{}", &self.id, mark, status, &self.text.trim_end())
+    /* Done, Exit 2, Killed, Stopped, Stopped (tty input), Running, ... as bash */
+    fn status_name(&self) -> String {
+        match self.status {
+            JobStatus::Running => "Running".to_string(),
+            JobStatus::Stopped => {
+                let sig = self.states.iter().find_map(|s| match s {
+                    ProcessState::Stopped(sig) => Some(*sig),
+                    _ => None,
+                });
+                match sig {
+                    Some(Signal::SIGTTIN) => "Stopped (tty input)".to_string(),
+                    Some(Signal::SIGTTOU) => "Stopped (tty output)".to_string(),
+                    _                     => "Stopped".to_string(),
+                }
+            },
+            JobStatus::Done | JobStatus::Invalid => match self.states.last() {
+                Some(ProcessState::Exited(0)) | None => "Done".to_string(),
+                Some(ProcessState::Exited(n))        => format!("Exit {}", n),
+                Some(ProcessState::Signaled(sig))    => proc::signal_description(*sig),
+                _                                    => "Done".to_string(),
+            },
+            JobStatus::Foreground => "ERROR".to_string(),
+        }
     }
 
-    pub fn print_status(&mut self, first: usize, second: usize) {
-        if self.status == 'I' {
+    /* the group leader, or the first process for a job in the group of the shell */
+    pub fn leader_pid(&self) -> Option<Pid> {
+        self.pgid.or(self.pids.first().copied())
+    }
+
+    /* with_pid: the pid after the mark as jobs -l */
+    pub fn print_status(&mut self, first: usize, second: usize, with_pid: bool) {
+        if self.status == JobStatus::Invalid {
             return;
         }
 
-        self.change_status_with_file();
-        println!("{}", &self.status_string(first, second));
-        if self.status == 'D' {
-            self.status = 'I';
+        self.check_of_finish();
+        let status = self.status_string(first, second);
+        match (with_pid, status.split_once(' '), self.leader_pid()) {
+            (true, Some((head, rest)), Some(pid)) => println!("{} {} {}", head, pid, rest),
+            _ => println!("{}", &status),
         }
-    }
-
-    pub fn change_status_with_file(&mut self) {
-        for p in &self.async_pids {
-            match proc::check_status_from_file(p.clone()) {
-                Some('T') => self.status = 'S',
-                Some('S') => self.status = 'R',
-                _         => (),
-            }
+        if self.status == JobStatus::Done {
+            self.status = JobStatus::Invalid;
         }
     }
 }
@@ -2,7 +2,7 @@
 //SPDX-License-Identifier: BSD-3-Clause
 
 use nix::unistd::Pid;
-use super::job::Job;
+use super::job::{Job, JobStatus, ProcessState};
 use crate::elements::command::Command;
 use super::proc;
 //use nix::unistd;
@@ -12,6 +12,7 @@ use super::proc;
 pub struct Jobs {
     pub foreground: Job,
     pub backgrounds: Vec<Job>, //0: current job, 1~: background jobs
+    pub proc_sub_pids: Vec<Pid>,
 }
 
 impl Jobs {
@@ -19,6 +20,7 @@ impl Jobs {
         Jobs {
             foreground: Job::new(&"".to_string(), &vec![], false),
             backgrounds: vec![],
+            proc_sub_pids: vec![],
         }
     }
 
@@ -52,17 +54,16 @@ impl Jobs {
         (id, id_second)
     }
 
-    fn to_background(&mut self, pid: Pid){
+    /* the stopped foreground job goes to the table with all of its processes */
+    fn to_background(&mut self){
         self.reduce_priority();
 
         let mut job = self.foreground.clone();
         job.priority = 0;
-        job.status = 'S';
-        job.signaled_bg = true;
+        job.status = JobStatus::Stopped;
         job.id = self.backgrounds.len()+1;
-        job.async_pids.push(pid);
         println!("{}", &job.status_string(job.id, 0));
-        self.add_job(job.clone());
+        self.add_job(job);
     }
 
     pub fn set_fg_job(&mut self, text: &String, commands: &Vec<Box<dyn Command>>) {
@@ -78,7 +79,6 @@ impl Jobs {
 
         if let Some(pid) = commands.last().unwrap().get_pid() {
             eprintln!("[{}] {}", bgjob.id, pid);
-            bgjob.async_pids.push(pid);
         }else{
             panic!("Bash internal error (before running background process)");
         }
@@ -87,15 +87,30 @@ impl Jobs {
         return;
     }
 
+    /* waits until every process of the job finishes or stops */
+    fn wait_job(job: &mut Job) {
+        for i in 0..job.pids.len() {
+            if job.states[i] != ProcessState::Running {
+                continue;
+            }
+            let (state, user, sys) = proc::wait_process(job.pids[i]);
+            job.states[i] = state;
+            job.user_time += user;
+            job.sys_time += sys;
+        }
+    }
+
     pub fn wait_fg_job(&mut self) -> Vec<i32> {
-        let mut pipestatus = vec![];
-        for p in self.foreground.pids.clone() {
-            let exit_status = self.wait_process(p);
-            pipestatus.push(exit_status);
+        Self::wait_job(&mut self.foreground);
+        for (pid, state) in self.foreground.pids.iter().zip(self.foreground.states.iter()) {
+            proc::report_signal(*pid, *state);
+        }
+        if self.foreground.is_stopped() {
+            self.to_background();
         }
 
-        self.foreground.status = 'D';
-        pipestatus
+        self.foreground.status = JobStatus::Done;
+        self.foreground.pipestatus()
     }
 
     pub fn wait_bg_job_at_foreground(&mut self, job_no: usize) -> Vec<i32> {
@@ -104,27 +119,80 @@ impl Jobs {
         }
 
         let pos = job_no - 1;
-        if self.backgrounds[pos].status != 'F' {
+        if self.backgrounds[pos].status != JobStatus::Foreground {
             return vec![];
         }
 
-        let mut pipestatus = vec![];
-        for p in self.backgrounds[pos].pids.clone() {
-            let exit_status = self.wait_process(p);
-            pipestatus.push(exit_status);
+        let job = &mut self.backgrounds[pos];
+        Self::wait_job(job);
+        if job.is_stopped() {
+            job.status = JobStatus::Stopped;
+            println!("{}", &job.status_string(job.id, 0));
+        }else{
+            job.status = JobStatus::Done;
         }
+        job.pipestatus()
+    }
 
-        self.backgrounds[pos].status = 'D';
-        pipestatus
+    fn position_of(&self, id: usize) -> Option<usize> {
+        self.backgrounds.iter().position(|j| j.id == id && j.status != JobStatus::Invalid)
     }
 
-    pub fn wait_process(&mut self, child: Pid) -> i32 {
-        let exit_status = proc::wait_process(child);
-        if exit_status == 147 || exit_status == 148 { //SIGTSTP or SIGSTOP
-            self.to_background(child);
+    /* %n, %+, %%, %-, %string (prefix of the command), and %?string (substring).
+     * Returns the position in backgrounds. */
+    pub fn find_job(&self, spec: &str) -> Result<usize, String> {
+        let name = match spec.strip_prefix('%') {
+            Some(n) => n,
+            None    => return Err(format!("{}: no such job", spec)),
+        };
+        let (first, second) = self.get_top_priority_id();
+
+        let found = match name {
+            "" | "%" | "+" => {
+                return self.position_of(first).ok_or("current: no such job".to_string());
+            },
+            "-" => self.position_of(second).or(self.position_of(first)),
+            n if n.parse::<usize>().is_ok() => self.position_of(n.parse::<usize>().unwrap()),
+            n => {
+                let (key, substring) = match n.strip_prefix('?') {
+                    Some(k) => (k, true),
+                    None    => (n, false),
+                };
+                let hits: Vec<usize> = (0..self.backgrounds.len())
+                    .filter(|i| self.backgrounds[*i].status != JobStatus::Invalid)
+                    .filter(|i| {
+                        let text = self.backgrounds[*i].text.trim_start();
+                        if substring {text.contains(key)}else{text.starts_with(key)}
+                    })
+                    .collect();
+
+                if hits.len() > 1 {
+                    return Err(format!("{}: ambiguous job spec", key));
+                }
+                hits.first().copied()
+            },
+        };
+
+        found.ok_or(format!("{}: no such job", spec))
+    }
+
+    /* the position of the job that has the process */
+    pub fn find_job_by_pid(&self, pid: Pid) -> Option<usize> {
+        self.backgrounds.iter().position(|j| j.status != JobStatus::Invalid && j.pids.contains(&pid))
+    }
+
+    pub fn wait_proc_sub(&mut self, pid: Pid) -> Option<i32> {
+        let pos = self.proc_sub_pids.iter().position(|p| *p == pid)?;
+        self.proc_sub_pids.remove(pos);
+        let (state, _, _) = proc::wait_process(pid);
+        Some(state.exit_status())
+    }
+
+    pub fn wait_proc_subs(&mut self) {
+        for p in self.proc_sub_pids.drain(..) {
+            proc::wait_process(p);
         }
-        exit_status
-    } 
+    }
 
     pub fn add_job(&mut self, added: Job) {
         self.backgrounds.push(added);
@@ -134,7 +202,7 @@ impl Jobs {
         while self.backgrounds.len() > 0 {
             let job = self.backgrounds.pop().unwrap();
 
-            if job.status != 'I' && job.status != 'F' {
+            if job.status != JobStatus::Invalid && job.status != JobStatus::Foreground {
                 self.backgrounds.push(job);
                 break;
             }
@@ -2,58 +2,230 @@
 //SPDX-License-Identifier: BSD-3-Clause
 
 use nix::unistd::Pid;
-use nix::sys::wait::{waitpid, WaitStatus, WaitPidFlag};
-use std::fs;
+use nix::sys::wait::{WaitStatus, WaitPidFlag};
+use nix::errno::Errno;
+use super::job::ProcessState;
+use std::ffi::CStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicUsize, Ordering};
+use std::time::Duration;
 
 use nix::sys::signal;
-use nix::sys::signal::{Signal, SigHandler};
+use nix::sys::signal::{Signal, SigHandler, SigAction, SaFlags, SigSet};
 
-pub fn wait_process(child: Pid) -> i32 {
-    let exit_status = match waitpid(child, Some(WaitPidFlag::WUNTRACED)) {
-        Ok(WaitStatus::Exited(_pid, status)) => {
-            status
-        },
-        Ok(WaitStatus::Signaled(pid, signal, _coredump)) => {
+/* A status change of a child. The SIGCHLD handler writes it to RING,
+ * and the shell moves it to RECORDS with SIGCHLD blocked. */
+struct Slot {
+    pid: AtomicI32,
+    status: AtomicI32,
+    user_usec: AtomicI64,
+    sys_usec: AtomicI64,
+}
+
+impl Slot {
+    const fn new() -> Slot {
+        Slot {
+            pid: AtomicI32::new(0),
+            status: AtomicI32::new(0),
+            user_usec: AtomicI64::new(0),
+            sys_usec: AtomicI64::new(0),
+        }
+    }
+}
+
+struct Record {
+    pid: Pid,
+    state: ProcessState,
+    user: Duration,
+    sys: Duration,
+}
+
+const RING_SIZE: usize = 256;
+static RING: [Slot; RING_SIZE] = [const { Slot::new() }; RING_SIZE];
+static RING_HEAD: AtomicUsize = AtomicUsize::new(0); //written only by the handler
+static RING_TAIL: AtomicUsize = AtomicUsize::new(0); //written only by the shell
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+fn timeval_to_duration(tv: libc::timeval) -> Duration {
+    Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+}
+
+/* waitpid that also returns the user and system time of the child */
+fn wait4(child: Pid, options: WaitPidFlag) -> (nix::Result<WaitStatus>, Duration, Duration) {
+    let mut status: i32 = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    let res = unsafe { libc::wait4(child.as_raw(), &mut status, options.bits(), &mut usage) };
+    if res == -1 {
+        return (Err(Errno::last()), Duration::ZERO, Duration::ZERO);
+    }
+
+    (WaitStatus::from_raw(Pid::from_raw(res), status),
+     timeval_to_duration(usage.ru_utime),
+     timeval_to_duration(usage.ru_stime))
+}
+
+fn usec_to_duration(usec: i64) -> Duration {
+    Duration::from_micros(usec.max(0) as u64)
+}
+
+/* reaps children that have changed their states while the ring has room.
+ * The rest are left to collect_records. Only async-signal-safe calls here. */
+fn reap_children() {
+    let flags = libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED;
+
+    loop {
+        let head = RING_HEAD.load(Ordering::Relaxed);
+        if head - RING_TAIL.load(Ordering::Acquire) >= RING_SIZE {
+            break;
+        }
+
+        let mut status: i32 = 0;
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        let pid = unsafe { libc::wait4(-1, &mut status, flags, &mut usage) };
+        if pid <= 0 {
+            break;
+        }
+
+        let slot = &RING[head % RING_SIZE];
+        slot.pid.store(pid, Ordering::Relaxed);
+        slot.status.store(status, Ordering::Relaxed);
+        slot.user_usec.store(usage.ru_utime.tv_sec * 1000000 + usage.ru_utime.tv_usec, Ordering::Relaxed);
+        slot.sys_usec.store(usage.ru_stime.tv_sec * 1000000 + usage.ru_stime.tv_usec, Ordering::Relaxed);
+        RING_HEAD.store(head + 1, Ordering::Release);
+    }
+}
+
+extern "C" fn handle_sigchld(_: libc::c_int) {
+    let saved_errno = Errno::last() as i32;
+    reap_children();
+    unsafe { *libc::__errno_location() = saved_errno };
+}
+
+pub fn set_sigchld_handler() {
+    let action = SigAction::new(SigHandler::Handler(handle_sigchld),
+                                SaFlags::SA_RESTART, SigSet::empty());
+    unsafe { signal::sigaction(Signal::SIGCHLD, &action) }.unwrap();
+}
+
+pub fn unset_sigchld_handler() {
+    unsafe { signal::signal(Signal::SIGCHLD, SigHandler::SigDfl) }.unwrap();
+}
+
+fn block_sigchld(block: bool) {
+    let mut set = SigSet::empty();
+    set.add(Signal::SIGCHLD);
+    let _ = if block {set.thread_block()}else{set.thread_unblock()};
+}
+
+/* moves the statuses written by the handler to RECORDS, and reaps the children
+ * left by the handler when the ring was full. SIGCHLD must be blocked. */
+fn collect_records(records: &mut Vec<Record>) {
+    loop {
+        move_ring_to(records);
+        reap_children();
+        if RING_HEAD.load(Ordering::Acquire) == RING_TAIL.load(Ordering::Relaxed) {
+            return;
+        }
+    }
+}
+
+fn move_ring_to(records: &mut Vec<Record>) {
+    let head = RING_HEAD.load(Ordering::Acquire);
+    for i in RING_TAIL.load(Ordering::Relaxed)..head {
+        let slot = &RING[i % RING_SIZE];
+        let pid = Pid::from_raw(slot.pid.load(Ordering::Relaxed));
+        let state = match WaitStatus::from_raw(pid, slot.status.load(Ordering::Relaxed)) {
+            Ok(ws) => ProcessState::from_wait_status(ws),
+            Err(_) => None,
+        };
+
+        if let Some(state) = state {
+            records.push(Record {
+                pid,
+                state,
+                user: usec_to_duration(slot.user_usec.load(Ordering::Relaxed)),
+                sys: usec_to_duration(slot.sys_usec.load(Ordering::Relaxed)),
+            });
+        }
+    }
+    RING_TAIL.store(head, Ordering::Release);
+}
+
+/* the latest state of the child, removing its records. SIGCHLD must be blocked. */
+fn take_record(pid: Pid) -> Option<(ProcessState, Duration, Duration)> {
+    let mut records = RECORDS.lock().unwrap();
+    collect_records(&mut records);
+
+    let mut ans = None;
+    records.retain(|r| {
+        if r.pid == pid {
+            ans = Some((r.state, r.user, r.sys));
+        }
+        r.pid != pid
+    });
+    ans
+}
+
+/* None if the child has not changed its state since the last call */
+pub fn take_state(pid: Pid) -> Option<ProcessState> {
+    block_sigchld(true);
+    let ans = take_record(pid);
+    block_sigchld(false);
+    ans.map(|r| r.0)
+}
+
+/* waits until the child finishes or stops */
+pub fn wait_process(child: Pid) -> (ProcessState, Duration, Duration) {
+    block_sigchld(true);
+    let (state, user, sys) = match take_record(child) {
+        Some(r) if r.0 != ProcessState::Running => r,
+        _ => wait_blocking(child),
+    };
+    block_sigchld(false);
+    (state, user, sys)
+}
+
+/* moves the statuses written by the handler to the records.
+ * False if no child has changed its state since the last call. */
+pub fn collect_states() -> bool {
+    block_sigchld(true);
+    let mut records = RECORDS.lock().unwrap();
+    let len = records.len();
+    collect_records(&mut records);
+    let ans = records.len() != len;
+    drop(records);
+    block_sigchld(false);
+    ans
+}
+
+pub fn report_signal(pid: Pid, state: ProcessState) {
+    if let ProcessState::Signaled(signal) = state {
+        if signal != Signal::SIGPIPE {
             eprintln!("Pid: {:?}, Signal: {:?}", pid, signal);
-            128+signal as i32
-        },
-        Ok(WaitStatus::Stopped(_pid, signal)) => {
-            128+signal as i32
-        },
-        Ok(unsupported) => {
-            eprintln!("Error: {:?}", unsupported);
-            1
+        }
+    }
+}
+
+fn wait_blocking(child: Pid) -> (ProcessState, Duration, Duration) {
+    let (result, user, sys) = wait4(child, WaitPidFlag::WUNTRACED);
+    let state = match result {
+        Ok(ws) => match ProcessState::from_wait_status(ws) {
+            Some(state) => state,
+            None => {
+                eprintln!("Error: {:?}", ws);
+                ProcessState::Exited(1)
+            },
         },
+        Err(Errno::ECHILD) => ProcessState::Exited(127), // already reaped
         Err(err) => {
             panic!("Error: {:?}", err);
         },
     };
 
-    exit_status
+    (state, user, sys)
 } 
 
-pub fn check_async_process(pid: Pid) -> bool {
-    match waitpid(pid, Some(WaitPidFlag::WNOHANG)) {
-        Ok(WaitStatus::StillAlive) => false,
-        Ok(_)                      => true, 
-        _                          => {eprintln!("ERROR");true},
-    }
-}
-
-pub fn check_status_from_file(pid: Pid) -> Option<char> {
-    let path = format!("/proc/{}/stat", pid);
-    match fs::read_to_string(path) {
-        Ok(source) => {
-            if let Some(s) = source.split(" ").nth(2) {
-                Some(s.chars().nth(0).unwrap())
-            }else{
-                None
-            }
-        },
-        _ => None,
-    }
-}
-
 pub fn set_signals() {
     unsafe {
         signal::signal(Signal::SIGINT, SigHandler::SigDfl).unwrap();
@@ -61,6 +233,7 @@ pub fn set_signals() {
         signal::signal(Signal::SIGTTIN, SigHandler::SigDfl).unwrap();
         signal::signal(Signal::SIGTTOU, SigHandler::SigDfl).unwrap();
         signal::signal(Signal::SIGTSTP, SigHandler::SigDfl).unwrap();
+        signal::signal(Signal::SIGPIPE, SigHandler::SigDfl).unwrap();
     }
 }
 
@@ -71,5 +244,37 @@ pub fn ignore_signals() {
         signal::signal(Signal::SIGTTIN, SigHandler::SigIgn).unwrap();
         signal::signal(Signal::SIGTTOU, SigHandler::SigIgn).unwrap();
         signal::signal(Signal::SIGTSTP, SigHandler::SigIgn).unwrap();
+        signal::signal(Signal::SIGPIPE, SigHandler::SigIgn).unwrap();
+    }
+}
+
+/* the signal table for kill and trap: names are given without "SIG" */
+pub fn signal_list() -> Vec<(i32, String)> {
+    Signal::iterator()
+        .map(|s| (s as i32, s.as_str().trim_start_matches("SIG").to_string()))
+        .collect()
+}
+
+pub fn signal_name(num: i32) -> Option<String> {
+    signal_list().into_iter().find(|s| s.0 == num).map(|s| s.1)
+}
+
+/* the description used in job reports, e.g. "Killed" and "Terminated" */
+pub fn signal_description(sig: Signal) -> String {
+    let desc = unsafe { libc::strsignal(sig as libc::c_int) };
+    if desc.is_null() {
+        return sig.as_str().to_string();
     }
+    unsafe { CStr::from_ptr(desc) }.to_string_lossy().to_string()
+}
+
+/* accepts INT, SIGINT, int, and 2; 0 is the null signal of kill */
+pub fn signal_number(spec: &str) -> Option<i32> {
+    if let Ok(n) = spec.parse::<i32>() {
+        return if n == 0 || signal_name(n).is_some() {Some(n)}else{None};
+    }
+
+    let upper = spec.to_uppercase();
+    let name = upper.trim_start_matches("SIG");
+    signal_list().into_iter().find(|s| s.1 == name).map(|s| s.0)
 }
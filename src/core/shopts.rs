@@ -6,7 +6,7 @@ use std::collections::HashMap;
 pub struct Shopts(HashMap<String, bool>);
 
 impl Shopts {
-    pub fn _get(self, key: &str) -> Option<bool> {
+    pub fn get(&self, key: &str) -> Option<bool> {
         match self.0.get(key) {
             Some(ans) => return Some(*ans),
             _ => return None,
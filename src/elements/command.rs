@@ -9,6 +9,7 @@ pub mod if_command;
 pub mod case_command;
 pub mod while_command;
 pub mod function_definition;
+pub mod coproc_command;
 
 use nix::unistd::Pid;
 //use nix::unistd;
@@ -16,6 +17,7 @@ use std::os::unix::prelude::RawFd;
 
 use crate::{Feeder, ShellCore}; 
 use crate::core::proc;
+use crate::file_descs::FileDescs;
 
 use self::double_paren::CommandDoubleParen;
 use self::if_command::CommandIf;
@@ -24,6 +26,7 @@ use self::paren::CommandParen;
 use self::brace::CommandBrace;
 use self::case_command::CommandCase;
 use self::function_definition::FunctionDefinition;
+use self::coproc_command::CommandCoproc;
 use self::simple::SimpleCommand;
 
 use std::process::exit;
@@ -46,6 +49,10 @@ pub enum CommandType {
     Null,
 }*/
 
+impl Clone for Box<dyn Command> {
+    fn clone(&self) -> Box<dyn Command> { self.boxed_clone() }
+}
+
 impl Debug for dyn Command {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("COMMAND")
@@ -63,8 +70,22 @@ pub trait Command {
              return;
         };
 
+        if self.runs_in_shell() {
+            let mut backups = vec![];
+            match self.set_parent_redirects(conf, &mut backups) {
+                Ok(()) => self.exec_elems(conf),
+                Err(s) => {
+                    eprintln!("{}", s);
+                    conf.set_var("?", "1");
+                },
+            }
+            FileDescs::restore(&mut backups);
+            return;
+        }
+
         match unsafe{fork()} {
             Ok(ForkResult::Child) => {
+                conf.join_job_group();
                 proc::set_signals();
                 self.set_group();
                 /*
@@ -75,6 +96,7 @@ pub trait Command {
                     eprintln!("{}", s);
                     exit(1);
                 }
+                conf.close_coproc_fds();
                 self.exec_elems(conf);
                 close(1).expect("Can't close a pipe end");
                 exit(conf.vars["?"].parse::<i32>().unwrap());
@@ -87,16 +109,21 @@ pub trait Command {
         }
     }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd);
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool);
     fn set_group_leader(&mut self);
     fn set_group(&mut self);
     fn get_pid(&self) -> Option<Pid>;
     fn get_pipe_end(&mut self) -> RawFd;
     fn get_pipe_out(&mut self) -> RawFd;
     fn get_text(&self) -> String;
+    fn boxed_clone(&self) -> Box<dyn Command>;
     fn set_child_io(&mut self, _conf: &mut ShellCore) -> Result<(), String> {Ok(())}
     fn exec_elems(&mut self, _conf: &mut ShellCore) {}
     fn no_connection(&self) -> bool { true }
+    /* only redirections, and not in the background */
+    fn runs_in_shell(&self) -> bool { false }
+    fn set_parent_redirects(&mut self, _conf: &mut ShellCore,
+                            _backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {Ok(())}
     fn set_pid(&mut self, _pid: Pid) {}
 }
 
@@ -104,10 +131,22 @@ pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<Box<dyn Command>
     if let Some(a) =      CommandIf::parse(text,conf)                  {Some(Box::new(a))}
     else if let Some(a) = CommandWhile::parse(text, conf)              {Some(Box::new(a))}
     else if let Some(a) = CommandCase::parse(text, conf)               {Some(Box::new(a))}
-    else if let Some(a) = CommandParen::parse(text, conf, false)       {Some(Box::new(a))}
+    else if let Some(a) = CommandCoproc::parse(text, conf)             {Some(Box::new(a))}
     else if let Some(a) = CommandDoubleParen::parse(text, conf, false) {Some(Box::new(a))}
+    else if let Some(a) = CommandParen::parse(text, conf, false)       {Some(Box::new(a))}
     else if let Some(a) = CommandBrace::parse(text, conf)              {Some(Box::new(a))}
     else if let Some(a) = FunctionDefinition::parse(text, conf)        {Some(Box::new(a))}
     else if let Some(a) = SimpleCommand::parse(text, conf)             {Some(Box::new(a))}
     else {None}
 }
+
+/* the commands that can be the body of a function */
+pub fn parse_compound(text: &mut Feeder, conf: &mut ShellCore) -> Option<Box<dyn Command>> {
+    if let Some(a) =      CommandIf::parse(text,conf)                  {Some(Box::new(a))}
+    else if let Some(a) = CommandWhile::parse(text, conf)              {Some(Box::new(a))}
+    else if let Some(a) = CommandCase::parse(text, conf)               {Some(Box::new(a))}
+    else if let Some(a) = CommandDoubleParen::parse(text, conf, false) {Some(Box::new(a))}
+    else if let Some(a) = CommandParen::parse(text, conf, false)       {Some(Box::new(a))}
+    else if let Some(a) = CommandBrace::parse(text, conf)              {Some(Box::new(a))}
+    else {None}
+}
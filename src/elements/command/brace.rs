@@ -8,7 +8,6 @@ use std::os::unix::prelude::RawFd;
 use crate::elements::script::Script;
 use crate::elements::redirect::Redirect;
 use crate::file_descs::*;
-use std::process::exit;
 use nix::unistd;
 
 fn tail_check(s: &String) -> bool{
@@ -24,7 +23,7 @@ fn tail_check(s: &String) -> bool{
     false
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandBrace {
     pub script: Script,
     text: String,
@@ -35,11 +34,9 @@ pub struct CommandBrace {
 }
 
 impl Command for CommandBrace {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec_elems(&mut self, conf: &mut ShellCore) {
              self.script.exec(conf);
-             if ! self.fds.no_connection() {
-                 exit(conf.vars["?"].parse::<i32>().unwrap());
-             }
     }
 
     fn set_pid(&mut self, pid: Pid) { self.pid = Some(pid); }
@@ -51,6 +48,11 @@ impl Command for CommandBrace {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
     fn no_connection(&self) -> bool { self.fds.no_connection() }
+    fn runs_in_shell(&self) -> bool { self.fds.no_pipe() && ! self.group_leader }
+    fn set_parent_redirects(&mut self, conf: &mut ShellCore,
+                            backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {
+        self.fds.set_parent_redirects(conf, backups)
+    }
 
     fn set_child_io(&mut self, conf: &mut ShellCore) -> Result<(), String> {
         self.fds.set_child_io(conf)
@@ -58,10 +60,11 @@ impl Command for CommandBrace {
 
     fn get_pid(&self) -> Option<Pid> { self.pid }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
@@ -82,7 +85,7 @@ impl CommandBrace {
     }
 
     pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<CommandBrace> {
-        if ! text.starts_with("{") {
+        if ! text.starts_with("{") || text.scanner_fd_var() != 0 {
             return None;
         }
 
@@ -4,7 +4,7 @@
 use crate::{ShellCore, Feeder};
 use crate::elements::command::Command;
 use std::os::unix::prelude::RawFd;
-//use crate::operators::ControlOperator;
+use crate::operators::ControlOperator;
 use crate::elements::script::Script;
 use crate::elements::redirect::Redirect;
 use nix::unistd::Pid;
@@ -15,10 +15,11 @@ use crate::elements::word::Word;
 use crate::bash_glob::glob_match;
 // use crate::elements::CommandElem;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandCase {
     pub word: Word,
-    pub conddo: Vec<(Vec<String>, Option<Script>)>,
+    pub conddo: Vec<(Vec<Word>, Option<Script>)>,
+    pub conddo_ends: Vec<ControlOperator>, // ;; ;& ;;&
     text: String,
     pid: Option<Pid>,
     fds: FileDescs,
@@ -26,6 +27,7 @@ pub struct CommandCase {
 }
 
 impl Command for CommandCase {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn get_pid(&self) -> Option<Pid> { self.pid }
     fn set_pid(&mut self, pid: Pid) { self.pid = Some(pid); }
     fn set_group(&mut self){
@@ -36,11 +38,17 @@ impl Command for CommandCase {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
     fn no_connection(&self) -> bool { self.fds.no_connection() }
+    fn runs_in_shell(&self) -> bool { self.fds.no_pipe() && ! self.group_leader }
+    fn set_parent_redirects(&mut self, conf: &mut ShellCore,
+                            backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {
+        self.fds.set_parent_redirects(conf, backups)
+    }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn set_child_io(&mut self, conf: &mut ShellCore)  -> Result<(), String> {
@@ -52,22 +60,28 @@ impl Command for CommandCase {
     fn get_text(&self) -> String { self.text.clone() }
 
     fn exec_elems(&mut self, conf: &mut ShellCore) {
-        let word_str = self.word.eval(conf).join(" ");
-
-        for (cond, doing) in &mut self.conddo {
-            let mut flag = false;
-            for c in cond {
-                if glob_match(c, &word_str) {
-                    if let Some(d) = doing {
-                        d.exec(conf);
-                    }
-                    flag = true;
-                    break;
-                }
+        let word_str = Word::remove_escape(&self.word.eval(conf).join(" "));
+        let nocase = conf.shopts.get("nocasematch") == Some(true);
+
+        let mut fall_through = false;
+        for (i, (cond, doing)) in self.conddo.iter_mut().enumerate() {
+            if ! fall_through && ! Self::pattern_match(cond, &word_str, nocase, conf) {
+                continue;
+            }
+
+            if let Some(d) = doing {
+                d.exec(conf);
             }
-            if flag {
+
+            if conf.return_flag {
                 break;
             }
+
+            match self.conddo_ends[i] {
+                ControlOperator::SemiAnd     => fall_through = true,
+                ControlOperator::SemiSemiAnd => fall_through = false,
+                _ => break,
+            }
         }
     }
 }
@@ -77,6 +91,7 @@ impl CommandCase {
         CommandCase {
             word: word, 
             conddo: vec![],
+            conddo_ends: vec![],
             text: "".to_string(),
             fds: FileDescs::new(),
             pid: None,
@@ -85,30 +100,55 @@ impl CommandCase {
     }
 
 
+    /* quoted parts of a pattern are escaped in Word::eval, so they match literally */
+    fn pattern_match(patterns: &mut [Word], word_str: &str, nocase: bool, conf: &mut ShellCore) -> bool {
+        for p in patterns {
+            let pattern = p.eval(conf).join(" ");
+            let matched = if nocase {
+                glob_match(&pattern.to_lowercase(), &word_str.to_lowercase())
+            }else{
+                glob_match(&pattern, &word_str.to_string())
+            };
+
+            if matched {
+                return true;
+            }
+        }
+        false
+    }
+
     fn parse_cond_do_pair(text: &mut Feeder, conf: &mut ShellCore, ans: &mut CommandCase) -> bool {
         let mut conds = vec![];
         ans.text += &text.request_next_line(conf);
 
+        if text.starts_with("(") {
+            ans.text += &text.consume(1);
+        }
+
         loop {
-            let pos = text.scanner_until_escape("|)");
-            if pos == 0 || pos == text.len() {
+            ans.text += &text.consume_blank();
+            if let Some(w) = Word::parse(text, conf, false) {
+                ans.text += &w.text.clone();
+                conds.push(w);
+            }else{
                 return false;
             }
-            conds.push(text.consume(pos));
-            ans.text += &conds.last().unwrap().clone();
+            ans.text += &text.consume_blank();
 
             if text.starts_with(")") {
-           // if text.nth(0) == ')' {
                 break;
-            }else{
+            }else if text.starts_with("|") {
                 ans.text += &text.consume(1);
+            }else{
+                return false;
             }
         }
 
         ans.text += &text.consume(1);
         ans.text += &text.request_next_line(conf);
+        ans.text += &text.consume_blank();
 
-        let doing = if text.len() >= 2 && text.starts_with( ";;") {
+        let doing = if text.starts_with( ";;") || text.starts_with( ";&") {
             None
         }else if let Some(s) = Script::parse(text, conf) {
             ans.text += &s.text;
@@ -119,11 +159,21 @@ impl CommandCase {
 
         ans.text += &text.request_next_line(conf);
 
-        if text.len() >= 2 && text.starts_with( ";;") {
+        let end = if text.starts_with( ";;&") {
+            ans.text += &text.consume(3);
+            ControlOperator::SemiSemiAnd
+        }else if text.starts_with( ";;") {
             ans.text += &text.consume(2);
-        }
+            ControlOperator::DoubleSemicolon
+        }else if text.starts_with( ";&") {
+            ans.text += &text.consume(2);
+            ControlOperator::SemiAnd
+        }else{
+            ControlOperator::NoChar
+        };
 
         ans.conddo.push( (conds, doing) );
+        ans.conddo_ends.push(end);
         true
     }
 
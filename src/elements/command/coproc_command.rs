@@ -0,0 +1,157 @@
+//SPDX-FileCopyrightText: 2023 Ryuichi Ueda ryuichiueda@gmail.com
+//SPDX-License-Identifier: BSD-3-Clause
+
+use crate::{ShellCore, Feeder};
+use crate::elements::command;
+use crate::elements::command::Command;
+use crate::elements::command::simple::SimpleCommand;
+use nix::unistd::{Pid, fork, ForkResult};
+use nix::unistd::{close, pipe};
+use nix::unistd;
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use std::os::unix::prelude::RawFd;
+use std::process::exit;
+use crate::file_descs::*;
+use crate::core::proc;
+
+fn starts_compound_command(text: &Feeder) -> bool {
+    if text.starts_with("{") || text.starts_with("(") {
+        return true;
+    }
+
+    for w in ["if", "while", "case"] {
+        if text.starts_with(w) && text.len() > w.len() && " \t\n".contains(text.nth(w.len())) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandCoproc {
+    pub name: String,
+    pub command: Box<dyn Command>,
+    text: String,
+    pid: Option<Pid>,
+    fds: FileDescs,
+    group_leader: bool,
+}
+
+impl Command for CommandCoproc {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
+    fn exec(&mut self, conf: &mut ShellCore) {
+        let from_shell = pipe().expect("Pipe cannot open");
+        let to_shell = pipe().expect("Pipe cannot open");
+
+        match unsafe{fork()} {
+            Ok(ForkResult::Child) => {
+                proc::set_signals();
+                conf.stop_job_control();
+                let pid = nix::unistd::getpid();
+                let _ = unistd::setpgid(pid, pid);
+
+                close(from_shell.1).expect("Can't close a pipe end");
+                close(to_shell.0).expect("Can't close a pipe end");
+                conf.close_coproc_fds();
+                FileDescs::dup_and_close(from_shell.0, 0);
+                FileDescs::dup_and_close(to_shell.1, 1);
+
+                self.command.exec(conf);
+                if let Some(p) = self.command.get_pid() {
+                    let (state, _, _) = proc::wait_process(p);
+                    conf.set_var("?", &state.exit_status().to_string());
+                }
+                exit(conf.vars["?"].parse::<i32>().unwrap());
+            },
+            Ok(ForkResult::Parent { child } ) => {
+                close(from_shell.0).expect("Can't close a pipe end");
+                close(to_shell.1).expect("Can't close a pipe end");
+                for fd in [to_shell.0, from_shell.1] {
+                    let _ = fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC));
+                    conf.coproc_fds.push(fd);
+                }
+
+                conf.arrays.insert(self.name.clone(),
+                                   vec!(to_shell.0.to_string(), from_shell.1.to_string()));
+                conf.set_var(&(self.name.clone() + "_PID"), &child.to_string());
+
+                let mut com = SimpleCommand::new();
+                com.group_leader = true;
+                com.pid = Some(child);
+                conf.jobs.add_bg_job(&self.text, &vec!(Box::new(com)));
+                conf.set_var("?", "0");
+            },
+            Err(err) => panic!("Failed to fork. {}", err),
+        }
+    }
+
+    fn set_pid(&mut self, pid: Pid) { self.pid = Some(pid); }
+    fn set_group(&mut self){
+        if self.group_leader {
+            let pid = nix::unistd::getpid();
+            let _ = unistd::setpgid(pid, pid);
+        }
+    }
+    fn set_group_leader(&mut self) { self.group_leader = true; }
+
+    fn get_pid(&self) -> Option<Pid> { self.pid }
+
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
+        self.fds.pipein = pin;
+        self.fds.pipeout = pout;
+        self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
+    }
+
+    fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
+    fn get_pipe_out(&mut self) -> RawFd { self.fds.pipeout }
+    fn get_text(&self) -> String { self.text.clone() }
+}
+
+impl CommandCoproc {
+    pub fn new(name: String, command: Box<dyn Command>) -> CommandCoproc{
+        CommandCoproc {
+            name,
+            command,
+            text: "".to_string(),
+            pid: None,
+            fds: FileDescs::new(),
+            group_leader: false,
+        }
+    }
+
+    pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<CommandCoproc> {
+        if ! text.starts_with("coproc") || text.len() < 7 || ! " \t".contains(text.nth(6)) {
+            return None;
+        }
+
+        let backup = text.clone();
+        let mut ans_text = text.consume(6);
+        ans_text += &text.consume_blank();
+
+        /* a NAME is given only before a compound command */
+        let mut name = "COPROC".to_string();
+        let name_backup = text.clone();
+        let pos = text.scanner_name(0);
+        if pos > 0 {
+            let name_text = text.consume(pos);
+            let blank = text.consume_blank();
+            if ! blank.is_empty() && starts_compound_command(text) {
+                name = name_text.clone();
+                ans_text += &(name_text + &blank);
+            }else{
+                text.rewind(name_backup);
+            }
+        }
+
+        if let Some(c) = command::parse(text, conf) {
+            ans_text += &c.get_text();
+            let mut ans = CommandCoproc::new(name, c);
+            ans.text = ans_text;
+            Some(ans)
+        }else{
+            text.rewind(backup);
+            None
+        }
+    }
+}
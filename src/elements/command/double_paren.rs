@@ -9,12 +9,18 @@ use crate::elements::redirect::Redirect;
 use crate::file_descs::*;
 //use crate::feeder::scanner::*;
 use crate::calculator::calculate;
+use crate::debuginfo::DebugInfo;
+use crate::elements::subword;
+use crate::elements::subword::Subword;
+use crate::elements::subword::string_non_quoted::SubwordStringNonQuoted;
+use crate::elements::word::Word;
+use crate::utils::combine;
 use nix::unistd;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandDoubleParen {
     text: String,
-    expression: String,
+    expression: Vec<Box<dyn Subword>>,
     pid: Option<Pid>, 
     pub substitution_text: String,
     pub substitution: bool,
@@ -23,8 +29,18 @@ pub struct CommandDoubleParen {
 }
 
 impl Command for CommandDoubleParen {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec(&mut self, conf: &mut ShellCore) {
-        self.substitution_text = calculate(self.expression.clone(), conf);
+        let expression = self.eval_expression(conf);
+        self.substitution_text = match calculate(expression.clone(), conf) {
+            Ok(ans) => ans,
+            Err(msg) => {
+                eprintln!("bash: {}: {}", expression.trim(), msg);
+                self.substitution_text.clear();
+                conf.set_var("?", "1");
+                return;
+            },
+        };
 
         let status = if self.substitution_text == "0" {
             "1"
@@ -44,10 +60,11 @@ impl Command for CommandDoubleParen {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
@@ -61,7 +78,7 @@ impl CommandDoubleParen {
            // script: None,
             pid: None,
             text: "".to_string(),
-            expression: "".to_string(),
+            expression: vec![],
             substitution_text: "".to_string(),
             substitution: false,
             fds: FileDescs::new(),
@@ -69,45 +86,88 @@ impl CommandDoubleParen {
         }
     }
 
-    // TODO: this function must parse ((1+$(echo a | wc -l)) for example. 
+    /* parameter, command, and arithmetic expansion, then quote removal */
+    fn eval_expression(&mut self, conf: &mut ShellCore) -> String {
+        let mut strings = vec![];
+        for sw in &mut self.expression {
+            let vs = sw.eval(conf, false);
+            if vs.iter().all(|v| v.is_empty()) { // an empty operand
+                continue;
+            }
+            strings = combine(&mut strings, vs);
+        }
+
+        let expression = strings.iter()
+            .map(|v| v.join(" "))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        Word::remove_escape(&expression)
+    }
+
+    /* Some(false) if a ) closes the first ( of (( alone */
+    fn parse_expression(text: &mut Feeder, conf: &mut ShellCore, ans: &mut CommandDoubleParen) -> Option<bool> {
+        let mut depth = 0;
+        loop {
+            if text.len() == 0 {
+                return None;
+            }
+
+            if depth == 0 && text.starts_with("))") {
+                return Some(true);
+            }
+
+            if text.starts_with("(") {
+                depth += 1;
+            }else if text.starts_with(")") {
+                if depth == 0 {
+                    return Some(false);
+                }
+                depth -= 1;
+            }
+
+            let sw = if let Some(a) = subword::parse_in_math(text, conf) {
+                a
+            }else{ /* parenthesis */
+                let pos = DebugInfo::init(text);
+                Box::new(SubwordStringNonQuoted::new(text.consume(1), pos))
+            };
+
+            ans.text += &sw.get_text();
+            ans.expression.push(sw);
+        }
+    }
+
     pub fn parse(text: &mut Feeder, conf: &mut ShellCore, substitution: bool) -> Option<CommandDoubleParen> {
         if text.len() < 2 || ! text.starts_with( "((") {
             return None;
         }
 
         let mut backup = text.clone();
-        let mut ans = CommandDoubleParen::new();
+        let mut ans;
         let mut input_success;
 
         loop{
+            ans = CommandDoubleParen::new();
             ans.text = text.consume(2);
 
-            let pos = text.scanner_until(0, ")");
-
-            if pos != text.len() {
-                ans.expression = text.consume(pos);
-                ans.text += &ans.expression.clone();
-            }else{
-                (backup, input_success) = text.rewind_feed_backup(&backup, conf);
-                if ! input_success {
-                    text.consume(text.len());
-                    return None;
-                }
-                continue;
-            }
-
-            if /*text.len() < 2 ||*/ ! text.starts_with( "))") {
-                (backup, input_success) = text.rewind_feed_backup(&backup, conf);
-                if ! input_success {
-                    text.consume(text.len());
+            match Self::parse_expression(text, conf, &mut ans) {
+                Some(true) => break,
+                Some(false) => { /* such as ((echo a) ) */
+                    text.rewind(backup);
                     return None;
-                }
-            }else{
-                break;
+                },
+                None => {
+                    (backup, input_success) = text.rewind_feed_backup(&backup, conf);
+                    if ! input_success {
+                        text.consume(text.len());
+                        return None;
+                    }
+                },
             }
         }
 
-        text.consume(2);
+        ans.text += &text.consume(2);
         if substitution {
             return Some(ans);
         }
@@ -9,12 +9,37 @@ use crate::elements::command::Command;
 use nix::unistd::Pid;
 use nix::unistd;
 use std::os::unix::prelude::RawFd;
+use std::cell::RefCell;
+use std::rc::Rc;
 use crate::FileDescs;
 
+/* A parsed function body shared by the definition and the function table.
+ * A call runs an idle copy of the body. A copy is made only when all the
+ * copies are running, that is, on a recursive call deeper than ever. */
 #[derive(Debug)]
+pub struct Function {
+    pub source: String,
+    pub body: Rc<Box<dyn Command>>,
+    idle_bodies: RefCell<Vec<Box<dyn Command>>>,
+}
+
+impl Function {
+    pub fn take_body(&self) -> Box<dyn Command> {
+        match self.idle_bodies.borrow_mut().pop() {
+            Some(body) => body,
+            None       => (*self.body).clone(),
+        }
+    }
+
+    pub fn return_body(&self, body: Box<dyn Command>) {
+        self.idle_bodies.borrow_mut().push(body);
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub name: String,
-    pub body: Box<dyn Command>,
+    pub body: Rc<Box<dyn Command>>,
     pid: Option<Pid>, 
     pub text: String,
     fds: FileDescs,
@@ -22,8 +47,14 @@ pub struct FunctionDefinition {
 }
 
 impl Command for FunctionDefinition {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec_elems(&mut self, conf: &mut ShellCore) {
-        conf.functions.insert(self.name.clone(), self.body.get_text());
+        let func = Function {
+            source: conf.current_source(),
+            body: Rc::clone(&self.body),
+            idle_bodies: RefCell::new(vec![]),
+        };
+        conf.functions.insert(self.name.clone(), Rc::new(func));
     }
     fn set_pid(&mut self, pid: Pid) { self.pid = Some(pid); }
     fn set_group(&mut self){
@@ -41,10 +72,11 @@ impl Command for FunctionDefinition {
 
     fn get_pid(&self) -> Option<Pid> { self.pid }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
@@ -56,7 +88,7 @@ impl FunctionDefinition {
     pub fn new(name: String, body: Box<dyn Command>, text: String) -> FunctionDefinition{
         FunctionDefinition {
             name: name,
-            body: body,
+            body: Rc::new(body),
             text: text,
             pid: None,
             fds: FileDescs::new(),
@@ -64,11 +96,31 @@ impl FunctionDefinition {
         }
     }
 
+    fn parse_paren_pair(text: &mut Feeder, ans_text: &mut String) -> bool {
+        if ! text.starts_with("(") {
+            return false;
+        }
+        let backup = text.clone();
+        *ans_text += &text.consume(1);
+        *ans_text += &text.consume_blank();
+
+        if ! text.starts_with(")") {
+            text.rewind(backup);
+            return false;
+        }
+        *ans_text += &text.consume(1);
+        true
+    }
+
+    /* name () compound-command [redirections]
+     * function name [()] compound-command [redirections]
+     * The redirections are kept in the body and applied on every call. */
     pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<FunctionDefinition> {
          let backup = text.clone();
          let mut ans_text = String::new();
 
-         if text.starts_with("function") {
+         let keyword = text.starts_with("function ") || text.starts_with("function\t");
+         if keyword {
             ans_text += &text.consume(8);
             ans_text += &text.consume_blank();
          }
@@ -82,28 +134,19 @@ impl FunctionDefinition {
          ans_text  += &name;
          ans_text += &text.consume_blank();
 
-
-         if ! text.starts_with("(") {
-             text.rewind(backup);
-             return None;
-         }
-         ans_text += &text.consume(1);
-         ans_text += &text.consume_blank();
- 
-         if ! text.starts_with(")") {
+         let paren = Self::parse_paren_pair(text, &mut ans_text);
+         if ! paren && ! keyword {
              text.rewind(backup);
              return None;
          }
-         ans_text += &text.consume(1);
-         ans_text += &text.consume_blank();
+         ans_text += &text.request_next_line(conf);
  
-         if let Some(c) = command::parse(text, conf){
+         if let Some(c) = command::parse_compound(text, conf){
              ans_text += &c.get_text();
              let ans = FunctionDefinition::new(name, c, ans_text);
              Some( ans )
          }else{
              text.rewind(backup);
-             eprintln!("NG '{}'", text._text());
              None
          }
     }
@@ -10,7 +10,7 @@ use nix::unistd::Pid;
 use crate::file_descs::*;
 use nix::unistd;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandIf {
     pub ifthen: Vec<(Script, Script)>,
     pub else_do: Option<Script>,
@@ -21,9 +21,13 @@ pub struct CommandIf {
 }
 
 impl Command for CommandIf {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec_elems(&mut self, conf: &mut ShellCore) {
         for pair in self.ifthen.iter_mut() {
              pair.0.exec(conf);
+             if conf.return_flag {
+                return;
+             }
              if conf.vars["?"] != "0" {
                 continue;
              }
@@ -45,6 +49,11 @@ impl Command for CommandIf {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
     fn no_connection(&self) -> bool { self.fds.no_connection() }
+    fn runs_in_shell(&self) -> bool { self.fds.no_pipe() && ! self.group_leader }
+    fn set_parent_redirects(&mut self, conf: &mut ShellCore,
+                            backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {
+        self.fds.set_parent_redirects(conf, backups)
+    }
 
     fn set_child_io(&mut self, conf: &mut ShellCore) -> Result<(), String> {
         self.fds.set_child_io(conf)
@@ -52,10 +61,11 @@ impl Command for CommandIf {
 
     fn get_pid(&self) -> Option<Pid> { self.pid }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
@@ -15,7 +15,7 @@ use nix::unistd::{close, pipe};
 //use crate::feeder::scanner::*;
 use crate::core::proc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandParen {
     pub script: Option<Script>,
     text: String,
@@ -27,17 +27,20 @@ pub struct CommandParen {
 }
 
 impl Command for CommandParen {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec(&mut self, conf: &mut ShellCore) {
         let p = pipe().expect("Pipe cannot open");
 
         match unsafe{fork()} {
             Ok(ForkResult::Child) => {
+                conf.join_job_group();
                 proc::set_signals();
                 self.set_group();
                 if let Err(s) = self.fds.set_child_io(conf){
                     eprintln!("{}", s);
                     exit(1);
                 }
+                conf.close_coproc_fds();
                 if let Some(s) = &mut self.script {
                     if self.substitution {
                         close(p.0).expect("Can't close a pipe end");
@@ -70,10 +73,11 @@ impl Command for CommandParen {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn get_pipe_end(&mut self) -> RawFd { self.fds.pipein }
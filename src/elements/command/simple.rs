@@ -10,18 +10,18 @@ use std::process::exit;
 use std::os::unix::prelude::RawFd;
 
 use crate::{ShellCore,Feeder};
+use crate::debuginfo::DebugInfo;
 use crate::utils::*;
 
 use crate::core::proc;
 use crate::elements::command::Command;
-use crate::elements::command;
 use crate::elements::word::Word;
 use crate::elements::redirect::Redirect;
 use crate::elements::substitution::Substitution;
 //use crate::feeder::scanner::*;
 use crate::file_descs::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimpleCommand {
     vars: Vec<Substitution>,
     pub args: Vec<Word>,
@@ -29,6 +29,7 @@ pub struct SimpleCommand {
     pub pid: Option<Pid>,
     fds: FileDescs,
     pub group_leader: bool,
+    lineno: u32,
 }
 
 fn is_reserve(s: &String) -> bool {
@@ -37,7 +38,9 @@ fn is_reserve(s: &String) -> bool {
 
 
 impl Command for SimpleCommand {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn exec(&mut self, core: &mut ShellCore) {
+        self.pid = None;
         if self.args.len() == 0 {
             self.set_vars(core);
             return;
@@ -47,6 +50,8 @@ impl Command for SimpleCommand {
             eprintln!("{}", self.text.trim_end());
         }
 
+        core.set_var("LINENO", &self.lineno.to_string());
+
         let mut args = self.eval(core);
         //eprintln!("NUM:{} {:?}", args.len(), &args); 
         core.set_var("_", &args[args.len()-1]);
@@ -64,16 +69,39 @@ impl Command for SimpleCommand {
             if self.run_on_this_process(&mut args, core) {
                 return;
             }
+        }else if self.fds.no_pipe() && ! self.group_leader
+              && (core.functions.contains_key(&args[0]) || core.builtins.contains_key(&args[0])) {
+            let mut backups = vec![];
+            match self.fds.set_parent_redirects(core, &mut backups) {
+                Ok(()) => if core.functions.contains_key(&args[0]) {
+                    self.exec_function(&mut args, core);
+                }else{
+                    core.keep_redirect_flag = false;
+                    self.run_on_this_process(&mut args, core);
+                    if core.keep_redirect_flag {
+                        core.keep_redirect_flag = false;
+                        FileDescs::keep(&mut backups);
+                    }
+                },
+                Err(s) => {
+                    eprintln!("{}", s);
+                    core.set_var("?", "1");
+                },
+            }
+            FileDescs::restore(&mut backups);
+            return;
         }
 
         match unsafe{fork()} {
             Ok(ForkResult::Child) => {
+                core.join_job_group();
                 proc::set_signals();
                 self.set_group();
                 if let Err(s) = self.fds.set_child_io(core){
                     eprintln!("{}", s);
                     exit(1);
                 }
+                core.close_coproc_fds();
                 self.exec_external_command(&mut args, core)
             },
             Ok(ForkResult::Parent { child } ) => {
@@ -84,10 +112,11 @@ impl Command for SimpleCommand {
         }
     }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn set_group_leader(&mut self) { self.group_leader = true; }
@@ -114,6 +143,7 @@ impl SimpleCommand {
             pid: None,
             fds: FileDescs::new(),
             group_leader: false,
+            lineno: 0,
         }
     }
 
@@ -156,29 +186,38 @@ impl SimpleCommand {
     }
 
     fn exec_function(&mut self, args: &mut Vec<String>, core: &mut ShellCore) {
-        let text = core.get_function(&args[0]).unwrap();
-
-        let mut feeder = Feeder::new_from(text);
-        //eprintln!("IN '{}'", feeder._text());
-        if let Some(mut f) = command::parse(&mut feeder, core) {
-         //   eprintln!("FUNCTION '{:?}'", f);
-            let backup = core.args.clone();
-            core.args = args.to_vec();
-            core.return_enable = true;
-            f.exec(core);
-            self.pid = f.get_pid();
-            core.args = backup;
-            core.return_enable = false;
-        }else{
-            panic!("Shell internal error on function");
+        let func = match core.get_function(&args[0]) {
+            Some(f) => f,
+            None    => return,
         };
-        //eprintln!("OUT '{}'", feeder._text());
+
+        if ! core.check_func_nest(&args[0]) {
+            return;
+        }
+
+        core.func_depth += 1;
+        core.push_call_frame(&args[0], &func.source, self.lineno);
+        let backup = core.args.clone();
+        let return_enable = core.return_enable;
+        core.args = args.to_vec();
+        core.return_enable = true;
+
+        let mut body = func.take_body();
+        body.exec(core);
+        self.pid = body.get_pid();
+        func.return_body(body);
+
+        core.args = backup;
+        core.return_enable = return_enable;
+        core.return_flag = core.abort_flag;
+        core.pop_call_frame();
+        core.func_depth -= 1;
     }
 
     fn exec_external_command(&mut self, args: &mut Vec<String>, core: &mut ShellCore) {
         if core.functions.contains_key(&args[0]) {
             self.exec_function(args, core);
-            exit(0);
+            exit(core.get_var("?").parse::<i32>().unwrap_or(1));
         }
 
         if let Some(func) = core.get_builtin(&args[0]) {
@@ -298,8 +337,9 @@ impl SimpleCommand {
     pub fn parse(text: &mut Feeder, core: &mut ShellCore) -> Option<SimpleCommand> {
         let backup = text.clone();
         let mut ans = SimpleCommand::new();
+        ans.lineno = DebugInfo::init(text).lineno;
 
-        if text.starts_with("{") {
+        if text.starts_with("{") && text.scanner_fd_var() == 0 {
             return None;
         };
 
@@ -10,7 +10,7 @@ use nix::unistd::Pid;
 use nix::unistd;
 use crate::file_descs::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandWhile {
     pub conddo: Option<(Script, Script)>,
     text: String,
@@ -20,6 +20,7 @@ pub struct CommandWhile {
 }
 
 impl Command for CommandWhile {
+    fn boxed_clone(&self) -> Box<dyn Command> { Box::new(self.clone()) }
     fn get_pid(&self) -> Option<Pid> { self.pid }
     fn set_pid(&mut self, pid: Pid) { self.pid = Some(pid); }
     fn set_group(&mut self){
@@ -30,11 +31,17 @@ impl Command for CommandWhile {
     }
     fn set_group_leader(&mut self) { self.group_leader = true; }
     fn no_connection(&self) -> bool { self.fds.no_connection() }
+    fn runs_in_shell(&self) -> bool { self.fds.no_pipe() && ! self.group_leader }
+    fn set_parent_redirects(&mut self, conf: &mut ShellCore,
+                            backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {
+        self.fds.set_parent_redirects(conf, backups)
+    }
 
-    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd) {
+    fn set_pipe(&mut self, pin: RawFd, pout: RawFd, pprev: RawFd, perr: bool) {
         self.fds.pipein = pin;
         self.fds.pipeout = pout;
         self.fds.prevpipein = pprev;
+        self.fds.pipeerr = perr;
     }
 
     fn set_child_io(&mut self, conf: &mut ShellCore) -> Result<(), String> {
@@ -49,11 +56,17 @@ impl Command for CommandWhile {
         loop {
             if let Some((cond, doing)) = &mut self.conddo {
                 cond.exec(conf);
+                if conf.return_flag {
+                    break;
+                }
                 if conf.vars["?"] != "0" {
                     conf.set_var("?", "0");
                     break;
                 }
                 doing.exec(conf);
+                if conf.return_flag {
+                    break;
+                }
             }
         }
     }
@@ -11,7 +11,7 @@ use std::process::exit;
 use super::command::simple::SimpleCommand;
 use crate::operators::ControlOperator;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Job {
     pub pipelines: Vec<Pipeline>,
     pub pipeline_ends: Vec<ControlOperator>,
@@ -39,6 +39,9 @@ impl Job {
     fn exec_job(&mut self, conf: &mut ShellCore) {
         let mut eop = ControlOperator::NoChar;
         for (i, p) in self.pipelines.iter_mut().enumerate() {
+            if conf.return_flag {
+                break;
+            }
             if conf.has_flag('d') {
                 eprintln!("{}", blue_string(&p.get_text()));
             }
@@ -58,6 +61,8 @@ impl Job {
         match unsafe{unistd::fork()} {
             Ok(ForkResult::Child) => {
                 proc::set_signals();
+                conf.stop_job_control();
+                conf.close_coproc_fds();
                 let pid = nix::unistd::getpid();
                 let _ = unistd::setpgid(pid, pid);
 
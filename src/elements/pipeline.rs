@@ -4,45 +4,121 @@
 use crate::{ShellCore, Feeder};
 use crate::elements::command::Command;
 use crate::operators::ControlOperator;
-use nix::unistd::pipe;
+use nix::unistd;
+use nix::unistd::{pipe, Pid};
 use crate::file_descs::FileDescs;
 use crate::elements::command;
+use crate::time_format;
+use nix::sys::resource::{getrusage, UsageWho};
+use nix::sys::time::TimeVal;
+use std::env;
+use std::time::{Duration, Instant};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Pipeline {
     pub commands: Vec<Box<dyn Command>>,
+    pipe_ands: Vec<bool>,
     pub text: String,
     pub is_bg: bool,
     pub job_no: u32,
     not_flag: bool,
+    time_flag: bool,
+    time_posix: bool,
+}
+
+fn timeval_to_duration(tv: TimeVal) -> Duration {
+    Duration::from_micros((tv.tv_sec() * 1000000 + tv.tv_usec()) as u64)
+}
+
+/* user and system time consumed by the shell itself */
+fn self_times() -> (Duration, Duration) {
+    match getrusage(UsageWho::RUSAGE_SELF) {
+        Ok(u) => (timeval_to_duration(u.user_time()), timeval_to_duration(u.system_time())),
+        _     => (Duration::ZERO, Duration::ZERO),
+    }
 }
 
 impl Pipeline {
     pub fn exec(&mut self, core: &mut ShellCore) {
+        let start = Instant::now();
+        let start_self_times = self_times();
+
         let len = self.commands.len();
         let mut prevfd = -1;
+        let mut pgid = None;
+        let grouped = self.is_bg || core.job_control();
+        if grouped {
+            core.job_pgid = Some(Pid::from_raw(0));
+            core.job_in_fg = ! self.is_bg;
+        }
         for (i, c) in self.commands.iter_mut().enumerate() {
             let mut p = (-1, -1);
             if i != len-1 {
                 p = pipe().expect("Pipe cannot open");
             };
-            c.set_pipe(p.0, p.1, prevfd);
+            c.set_pipe(p.0, p.1, prevfd, self.pipe_ands[i]);
             if self.is_bg && i == 0 {
                 c.set_group_leader();
             }
+            let proc_subs = core.proc_sub_fds.len();
             c.exec(core);
-            FileDescs::set_parent_io(c.get_pipe_out());
+            core.close_proc_subs(proc_subs);
+            if grouped {
+                Self::join_group(core, c.get_pid(), &mut pgid);
+            }
+            FileDescs::set_parent_io(c.get_pipe_out(), prevfd);
             prevfd = c.get_pipe_end();
         }
+        core.job_pgid = None;
+
+        self.set_job_and_wait(core, pgid);
+
+        if self.time_flag && ! self.is_bg {
+            self.print_time(core, start, start_self_times);
+        }
+    }
+
+    /* also done by the parent so that the job can be signaled right after fork */
+    fn join_group(core: &mut ShellCore, pid: Option<Pid>, pgid: &mut Option<Pid>) {
+        if let Some(p) = pid {
+            let leader = *pgid.get_or_insert(p);
+            let _ = unistd::setpgid(p, leader);
+            if core.job_pgid != Some(leader) {
+                core.job_pgid = Some(leader);
+                if core.job_in_fg {
+                    core.give_terminal_to(leader);
+                }
+            }
+        }
+    }
+
+    fn print_time(&self, core: &mut ShellCore, start: Instant, start_self_times: (Duration, Duration)) {
+        let fmt = if self.time_posix {
+            time_format::POSIX_FORMAT.to_string()
+        }else if let Some(f) = core.vars.get("TIMEFORMAT") {
+            f.clone()
+        }else if let Ok(f) = env::var("TIMEFORMAT") {
+            f
+        }else{
+            time_format::DEFAULT_FORMAT.to_string()
+        };
+
+        if fmt.is_empty() {
+            return;
+        }
 
-        self.set_job_and_wait(core);
+        let (self_user, self_sys) = self_times();
+        let user = core.jobs.foreground.user_time + self_user - start_self_times.0;
+        let sys = core.jobs.foreground.sys_time + self_sys - start_self_times.1;
+        eprintln!("{}", time_format::format(&fmt, start.elapsed(), user, sys));
     }
 
-    fn set_job_and_wait(&mut self, core: &mut ShellCore) {
+    fn set_job_and_wait(&mut self, core: &mut ShellCore, pgid: Option<Pid>) {
         if self.is_bg {
             core.jobs.add_bg_job(&self.text, &self.commands);
         }else{
             core.jobs.set_fg_job(&self.text, &self.commands);
+            core.jobs.foreground.pgid = pgid;
             core.wait_job();
             if self.not_flag {
                 core.reverse_exit_status();
@@ -55,8 +131,11 @@ impl Pipeline {
     pub fn new() -> Pipeline{
         Pipeline {
             commands: vec![],
+            pipe_ands: vec![],
             text: "".to_string(),
             not_flag: false,
+            time_flag: false,
+            time_posix: false,
             is_bg: false,
             job_no: 0,
         }
@@ -71,9 +150,37 @@ impl Pipeline {
         }
     }
 
+    fn ends_word(text: &Feeder, pos: usize) -> bool {
+        text.len() == pos || " \t\n;&|".contains(text.nth(pos))
+    }
+
+    /* time [-p] */
+    fn set_time(text: &mut Feeder, ans: &mut Pipeline) {
+        if ! text.starts_with("time") || ! Pipeline::ends_word(text, 4) {
+            return;
+        }
+        ans.time_flag = true;
+        ans.text += &text.consume(4);
+        ans.text += &text.consume_blank();
+
+        if text.starts_with("-p") && Pipeline::ends_word(text, 2) {
+            ans.time_posix = true;
+            ans.text += &text.consume(2);
+            ans.text += &text.consume_blank();
+        }
+    }
+
+    /* time can stand alone at the end of a command */
+    fn ends_command(text: &Feeder) -> bool {
+        text.len() == 0 || text.starts_with(";") || text.starts_with("\n")
+            || (text.starts_with("&") && ! text.starts_with("&&"))
+    }
+
     pub fn parse(text: &mut Feeder, core: &mut ShellCore) -> Option<Pipeline> {
+        let backup = text.clone();
         let mut ans = Pipeline::new();
         ans.text += &text.consume_blank();
+        Pipeline::set_time(text, &mut ans);
         if text.starts_with( "!") {
             ans.not_flag = true;
             ans.text += &text.consume(1);
@@ -87,6 +194,7 @@ impl Pipeline {
                 ans.text += &c.get_text();
                 ans.commands.push(c);
                 (_, op) = text.scanner_control_op();
+                ans.pipe_ands.push(op == Some(ControlOperator::PipeAnd));
                 Pipeline::set_control_op(text, &mut ans);
             }else{
                 break;
@@ -116,11 +224,12 @@ impl Pipeline {
             }
         }
 
-        ans.text += &text.consume_blank_return();
-        if ans.commands.len() > 0 {
-            Some(ans)
-        }else{
-            None
+        if ans.commands.len() == 0 && ! (ans.time_flag && ! ans.not_flag && Pipeline::ends_command(text)) {
+            text.rewind(backup);
+            return None;
         }
+
+        ans.text += &text.consume_blank_return();
+        Some(ans)
     }
 }
@@ -6,16 +6,23 @@ use crate::Feeder;
 use crate::ShellCore;
 use crate::operators::RedirectOp;
 use crate::elements::word::Word;
+use crate::utils::eval_glob;
+use crate::elements::subword;
+use crate::elements::subword::Subword;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Redirect {
     pub text: String,
     pub pos: DebugInfo,
     pub left_fd: i32,
+    pub left_fd_given: bool,
+    pub fd_var: Option<String>,
     pub right_fd: i32,
     pub redirect_type: RedirectOp,
     pub path: String,
     pub right_word: Option<Word>,
+    pub here_doc: String,
+    here_doc_subwords: Vec<Box<dyn Subword>>,
 }
 
 impl Redirect {
@@ -24,33 +31,109 @@ impl Redirect {
             text: String::new(),
             pos: DebugInfo::init(text),
             left_fd: -1,
+            left_fd_given: false,
+            fd_var: None,
             right_fd: -1,
             redirect_type: RedirectOp::NoRedirect,
             path: String::new(),
             right_word: None,
+            here_doc: String::new(),
+            here_doc_subwords: vec![],
         }
     }
 
-    pub fn eval(&mut self, conf: &mut ShellCore) -> String {
-        if let Some(a) = &mut self.right_word {
-            let strings = a.eval(conf);
-            if strings.len() == 1 {
-                return a.eval(conf)[0].clone();
-            }/*else if strings.len() > 1 {
-                eprintln!("bash: {}: ambiguous redirect", &a.text);
-            }*/
+    /* {name}>&- and {name}<&- */
+    pub fn closes_fd_var(&self) -> bool {
+        self.fd_var.is_some()
+        && (self.redirect_type == RedirectOp::OutputAnd || self.redirect_type == RedirectOp::InputAnd)
+        && self.right_word.as_ref().map(|w| w.text.as_str()) == Some("-")
+    }
+
+    /* the word must expand to exactly one path */
+    pub fn eval(&mut self, conf: &mut ShellCore) -> Result<String, String> {
+        let a = match &mut self.right_word {
+            Some(a) => a,
+            None    => return Ok(String::new()),
+        };
+
+        let mut strings = vec![];
+        for s in a.eval(conf) {
+            strings.append(&mut eval_glob(&s));
         }
 
-        String::new()
+        let quoted = a.text.contains('"') || a.text.contains('\'');
+        match strings.len() {
+            0 if quoted => Ok(String::new()),
+            1 if quoted || ! strings[0].is_empty() => Ok(Word::remove_escape(&strings[0])),
+            _ => Err(format!("bash: {}: ambiguous redirect", &a.text)),
+        }
+    }
+
+    /* the body is kept as it is when any part of the delimiter is quoted */
+    pub fn eval_here_doc(&mut self, conf: &mut ShellCore) -> String {
+        if self.here_doc_subwords.is_empty() {
+            return self.here_doc.clone();
+        }
+
+        conf.in_double_quot = true;
+        let mut ans = String::new();
+        for sw in &mut self.here_doc_subwords {
+            let strings: Vec<String> = sw.eval(conf, false).iter().map(|v| v.join(" ")).collect();
+            ans += &strings.join(" ");
+        }
+        conf.in_double_quot = false;
+        ans
+    }
+
+    /* expanded without word splitting or globbing */
+    pub fn eval_here_string(&mut self, conf: &mut ShellCore) -> String {
+        match &mut self.right_word {
+            Some(a) => Word::remove_escape(&a.eval(conf).join(" ")) + "\n",
+            None    => "\n".to_string(),
+        }
+    }
+
+    fn parse_here_doc(&mut self, text: &mut Feeder, conf: &mut ShellCore) {
+        let word = match &self.right_word {
+            Some(w) => w.text.clone(),
+            None    => return,
+        };
+
+        let quoted = word.contains(|c| "'\"\\".contains(c));
+        let mut delim = String::new();
+        let mut escaped = false;
+        for ch in word.chars() {
+            if escaped || (ch != '\\' && ch != '\'' && ch != '"') {
+                delim.push(ch);
+            }
+            escaped = ! escaped && ch == '\\';
+        }
+
+        let strip_tabs = self.redirect_type == RedirectOp::HereDocTab;
+        self.here_doc = text.consume_heredoc(&delim, strip_tabs, conf);
+        if quoted {
+            return;
+        }
+
+        let mut body = Feeder::new_from(self.here_doc.clone());
+        while let Some(sw) = subword::parse_in_heredoc(&mut body, conf) {
+            self.here_doc_subwords.push(sw);
+        }
     }
 
     pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<Redirect> {
         let mut ans = Redirect::new(text);
         let backup = text.clone();
+        let var_pos = text.scanner_fd_var();
         let pos = text.scanner_number(0);
-        if pos > 0 {
+        if var_pos > 0 {
+            ans.text += &text.consume(var_pos);
+            ans.fd_var = Some(ans.text[1..var_pos-1].to_string());
+            ans.left_fd_given = true;
+        }else if pos > 0 {
             if let Ok(num) = text.from_to(0, pos).parse::<i32>() {
                 ans.left_fd = num;
+                ans.left_fd_given = true;
                 ans.text += &text.consume(pos);
             }else{
                 return None;
@@ -58,7 +141,7 @@ impl Redirect {
         }
 
         let (pos, red) = text.scanner_redirect();
-        if pos == 0 {
+        if pos == 0 || text.starts_with("<(") || text.starts_with(">(") {
             text.rewind(backup);
             return None;
         }
@@ -68,9 +151,17 @@ impl Redirect {
         ans.text += &text.consume_blank();
 
         if ans.left_fd == -1 {
-            if ans.redirect_type == RedirectOp::Input {
+            if ans.redirect_type == RedirectOp::Input
+            || ans.redirect_type == RedirectOp::InOut
+            || ans.redirect_type == RedirectOp::HereDoc
+            || ans.redirect_type == RedirectOp::HereDocTab
+            || ans.redirect_type == RedirectOp::HereStr
+            || ans.redirect_type == RedirectOp::InputAnd {
                 ans.left_fd = 0;
-            }else if ans.redirect_type == RedirectOp::Output {
+            }else if ans.redirect_type == RedirectOp::Output
+                  || ans.redirect_type == RedirectOp::Clobber
+                  || ans.redirect_type == RedirectOp::OutputAnd
+                  || ans.redirect_type == RedirectOp::Append {
                 ans.left_fd = 1;
             }
         }
@@ -84,6 +175,11 @@ impl Redirect {
             return None;
         };
 
+        if ans.redirect_type == RedirectOp::HereDoc
+        || ans.redirect_type == RedirectOp::HereDocTab {
+            ans.parse_here_doc(text, conf);
+        }
+
         Some(ans)
     }
 }
@@ -4,7 +4,7 @@
 use crate::{ShellCore, Feeder};
 use crate::elements::job::Job;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Script {
     pub list: Vec<Job>,
     pub text: String,
@@ -16,7 +16,6 @@ impl Script {
             j.exec(conf);
 
             if conf.return_flag {
-                conf.return_flag = false;
                 return;
             }
         }
@@ -5,8 +5,9 @@ use crate::ShellCore;
 use crate::debuginfo::DebugInfo;
 use crate::Feeder;
 use crate::elements::value::Value;
+use crate::elements::word::Word;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Substitution {
     pub text: String,
     pub name: String,
@@ -23,7 +24,7 @@ impl Substitution {
         for s in self.value.eval(conf){
             v += &s;
         }
-        ans.push(v);
+        ans.push(Word::remove_escape(&v));
 
         ans
     }
@@ -5,6 +5,7 @@ pub mod braced;
 pub mod command_substitution;
 pub mod double_quoted;
 pub mod math_substitution;
+pub mod process_substitution;
 pub mod string_double_quoted;
 pub mod string_non_quoted;
 pub mod single_quoted;
@@ -15,8 +16,10 @@ use crate::{Feeder, ShellCore};
 
 use self::command_substitution::SubwordCommandSubstitution;
 use self::math_substitution::SubwordMathSubstitution;
+use self::process_substitution::SubwordProcessSubstitution;
 use self::string_non_quoted::SubwordStringNonQuoted;
 use self::double_quoted::SubwordDoubleQuoted;
+use self::string_double_quoted::SubwordStringDoubleQuoted;
 use self::single_quoted::SubwordSingleQuoted;
 use self::braced::SubwordBraced;
 use self::variable::SubwordVariable;
@@ -26,9 +29,14 @@ use std::fmt;
 pub trait Subword {
     fn eval(&mut self, _conf: &mut ShellCore, remove_lf: bool) -> Vec<Vec<String>>;
     fn get_text(&self) -> String;
+    fn boxed_clone(&self) -> Box<dyn Subword>;
     fn permit_lf(&self) -> bool {false}
 }
 
+impl Clone for Box<dyn Subword> {
+    fn clone(&self) -> Box<dyn Subword> { self.boxed_clone() }
+}
+
 impl Debug for dyn Subword {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("SUBWORD")
@@ -42,6 +50,7 @@ impl Debug for dyn Subword {
 pub fn parse_in_arg(text: &mut Feeder, conf: &mut ShellCore, is_in_brace: bool) -> Option<Box<dyn Subword>> {
     if let Some(a) = SubwordMathSubstitution::parse(text, conf)                   {Some(Box::new(a))}
     else if let Some(a) = SubwordCommandSubstitution::parse(text, conf)           {Some(Box::new(a))}
+    else if let Some(a) = SubwordProcessSubstitution::parse(text, conf)           {Some(Box::new(a))}
     else if let Some(a) = SubwordVariable::parse(text)                            {Some(Box::new(a))}
     else if let Some(a) = SubwordBraced::parse(text, conf)                        {Some(Box::new(a))}
     else if let Some(a) = SubwordSingleQuoted::parse(text, conf)                  {Some(Box::new(a))}
@@ -59,3 +68,21 @@ pub fn parse_in_value(text: &mut Feeder, conf: &mut ShellCore) -> Option<Box<dyn
     else if let Some(a) = SubwordStringNonQuoted::parse(text, false, true)    {Some(Box::new(a))}
     else {None}
 }
+
+pub fn parse_in_heredoc(text: &mut Feeder, conf: &mut ShellCore) -> Option<Box<dyn Subword>> {
+    if let Some(a) = SubwordStringDoubleQuoted::parse_in_heredoc(text)          {Some(Box::new(a))}
+    else if let Some(a) = SubwordMathSubstitution::parse(text, conf)            {Some(Box::new(a))}
+    else if let Some(a) = SubwordCommandSubstitution::parse(text, conf)         {Some(Box::new(a))}
+    else if let Some(a) = SubwordVariable::parse(text)                          {Some(Box::new(a))}
+    else {None}
+}
+
+pub fn parse_in_math(text: &mut Feeder, conf: &mut ShellCore) -> Option<Box<dyn Subword>> {
+    if let Some(a) = SubwordMathSubstitution::parse(text, conf)               {Some(Box::new(a))}
+    else if let Some(a) = SubwordCommandSubstitution::parse(text, conf)       {Some(Box::new(a))}
+    else if let Some(a) = SubwordVariable::parse(text)                        {Some(Box::new(a))}
+    else if let Some(a) = SubwordSingleQuoted::parse(text, conf)              {Some(Box::new(a))}
+    else if let Some(a) = SubwordDoubleQuoted::parse(text, conf)              {Some(Box::new(a))}
+    else if let Some(a) = SubwordStringNonQuoted::parse_in_math(text)         {Some(Box::new(a))}
+    else {None}
+}
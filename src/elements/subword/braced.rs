@@ -11,7 +11,7 @@ use crate::elements::word::word_in_brace;
 use crate::elements::subword::Subword;
 use crate::utils::combine_with;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordBraced {
     pub text: String,
     pub pos: DebugInfo,
@@ -20,6 +20,7 @@ pub struct SubwordBraced {
 }
 
 impl Subword for SubwordBraced {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn eval(&mut self, conf: &mut ShellCore, _: bool) -> Vec<Vec<String>> {
         if self.complete {
             self.eval_complete(conf)
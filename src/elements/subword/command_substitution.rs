@@ -9,7 +9,7 @@ use crate::elements::subword::Subword;
 use crate::elements::command::Command;
 use crate::elements::command::paren::CommandParen;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordCommandSubstitution {
     pub text: String,
     pub pos: DebugInfo,
@@ -17,9 +17,12 @@ pub struct SubwordCommandSubstitution {
 }
 
 impl Subword for SubwordCommandSubstitution {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn eval(&mut self, conf: &mut ShellCore, remove_lf: bool) -> Vec<Vec<String>> {
         self.com.substitution = true;
+        let job_pgid = conf.job_pgid.take(); // not a process of the job
         self.com.exec(conf);
+        conf.job_pgid = job_pgid;
 
         if ! remove_lf {
             return vec!(vec!(self.com.substitution_text.clone()));
@@ -11,7 +11,7 @@ use crate::elements::subword::variable::SubwordVariable;
 use crate::elements::subword::command_substitution::SubwordCommandSubstitution;
 use crate::utils::combine;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordDoubleQuoted {
     pub text: String,
     pub pos: DebugInfo,
@@ -19,6 +19,7 @@ pub struct SubwordDoubleQuoted {
 }
 
 impl Subword for SubwordDoubleQuoted {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn eval(&mut self, conf: &mut ShellCore, _: bool) -> Vec<Vec<String>> {
         conf.in_double_quot = true;
 
@@ -36,7 +37,9 @@ impl Subword for SubwordDoubleQuoted {
         for ss in strings {
             let mut anselem = vec![];
             for s in ss {
-                let x = s.replace("*", "\\*");
+                let x = s.replace("*", "\\*")
+                         .replace("?", "\\?")
+                         .replace("[", "\\[");
                 anselem.push(x);
             }
             ans.push(anselem);
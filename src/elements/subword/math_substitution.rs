@@ -9,7 +9,7 @@ use crate::elements::subword::Subword;
 use crate::elements::command::Command;
 use crate::elements::command::double_paren::CommandDoubleParen;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordMathSubstitution {
     pub text: String,
     pub pos: DebugInfo,
@@ -18,6 +18,7 @@ pub struct SubwordMathSubstitution {
 }
 
 impl Subword for SubwordMathSubstitution {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn eval(&mut self, conf: &mut ShellCore, _: bool) -> Vec<Vec<String>> {
         self.com.substitution = true;
         self.com.exec(conf);
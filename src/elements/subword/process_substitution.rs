@@ -0,0 +1,87 @@
+//SPDX-FileCopyrightText: 2023 Ryuichi Ueda ryuichiueda@gmail.com
+//SPDX-License-Identifier: BSD-3-Clause
+
+use crate::debuginfo::DebugInfo;
+use crate::ShellCore;
+use crate::Feeder;
+
+use crate::elements::subword::Subword;
+use crate::elements::command::Command;
+use crate::elements::command::paren::CommandParen;
+use crate::file_descs::FileDescs;
+use crate::core::proc;
+use nix::unistd::{close, fork, pipe, ForkResult};
+use std::process::exit;
+
+/* <(command) or >(command) */
+#[derive(Debug, Clone)]
+pub struct SubwordProcessSubstitution {
+    pub text: String,
+    pub pos: DebugInfo,
+    pub com: CommandParen,
+    pub is_input: bool, // true: <( ), false: >( )
+}
+
+impl Subword for SubwordProcessSubstitution {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
+    fn eval(&mut self, conf: &mut ShellCore, _remove_lf: bool) -> Vec<Vec<String>> {
+        let p = pipe().expect("Pipe cannot open");
+        let (parent_end, child_end, child_fd) = if self.is_input {
+            (p.0, p.1, 1)
+        }else{
+            (p.1, p.0, 0)
+        };
+
+        match unsafe{fork()} {
+            Ok(ForkResult::Child) => {
+                proc::set_signals();
+                conf.stop_job_control();
+                close(parent_end).expect("Can't close a pipe end");
+                conf.close_proc_subs(0);
+                conf.close_coproc_fds();
+                FileDescs::dup_and_close(child_end, child_fd);
+                if let Some(s) = &mut self.com.script {
+                    s.exec(conf);
+                }
+                exit(conf.get_var("?").parse::<i32>().unwrap_or(1));
+            },
+            Ok(ForkResult::Parent { child } ) => {
+                close(child_end).expect("Can't close a pipe end");
+                conf.proc_sub_fds.push(parent_end);
+                conf.jobs.proc_sub_pids.push(child);
+            },
+            Err(err) => panic!("Failed to fork. {}", err),
+        }
+
+        vec!(vec!(format!("/dev/fd/{}", parent_end)))
+    }
+
+    fn get_text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+impl SubwordProcessSubstitution {
+    pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<SubwordProcessSubstitution> {
+        if ! text.starts_with("<(") && ! text.starts_with(">(") {
+            return None;
+        }
+
+        let backup = text.clone();
+        let head = text.consume(1);
+
+        if let Some(e) = CommandParen::parse(text, conf, true){
+            let ans = SubwordProcessSubstitution {
+                text: head.clone() + &e.get_text(),
+                pos: DebugInfo::init(text),
+                com: e,
+                is_input: head == "<",
+            };
+
+            Some(ans)
+        }else{
+            text.rewind(backup);
+            None
+        }
+    }
+}
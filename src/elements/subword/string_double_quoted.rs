@@ -8,7 +8,7 @@ use crate::Feeder;
 
 use crate::elements::subword::Subword;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordStringDoubleQuoted {
     pub text: String,
     pub pos: DebugInfo,
@@ -16,6 +16,7 @@ pub struct SubwordStringDoubleQuoted {
 }
 
 impl Subword for SubwordStringDoubleQuoted {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn get_text(&self) -> String {
         self.text.clone()
     }
@@ -38,6 +39,33 @@ impl SubwordStringDoubleQuoted {
         }
     }
 
+    /* backslashes quote only $, `, \\, and newline in here-documents */
+    pub fn parse_in_heredoc(text: &mut Feeder) -> Option<SubwordStringDoubleQuoted> {
+        let mut pos = text.scanner_heredoc_word();
+        if pos == 0 && text.starts_with("$") {
+            match text.chars_after(1).next() {
+                Some(c) if c.is_ascii_alphanumeric() || "_{(?*@$#!-:".contains(c) => {},
+                _ => pos = 1,
+            }
+        }
+        if pos == 0 {
+            return None;
+        }
+
+        let pos_info = DebugInfo::init(text);
+        let mut ans = String::new();
+        let raw = text.consume(pos);
+        let mut chars = raw.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (ch, chars.peek()) {
+                ('\\', Some('\n')) => {chars.next();},
+                ('\\', Some(c)) if "$`\\".contains(*c) => ans.push(chars.next().unwrap()),
+                _ => ans.push(ch),
+            }
+        }
+        Some( SubwordStringDoubleQuoted::new(ans, pos_info) )
+    }
+
     pub fn parse(text: &mut Feeder, conf: &mut ShellCore) -> Option<SubwordStringDoubleQuoted> {
         let mut pos = text.scanner_double_quoted_word();
         while pos == text.len() {
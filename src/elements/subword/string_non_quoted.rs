@@ -8,7 +8,7 @@ use crate::Feeder;
 
 use crate::elements::subword::Subword;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordStringNonQuoted {
     pub text: String,
     pub pos: DebugInfo,
@@ -16,6 +16,7 @@ pub struct SubwordStringNonQuoted {
 }
 
 impl Subword for SubwordStringNonQuoted {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn get_text(&self) -> String {
         self.text.clone()
     }
@@ -32,7 +33,7 @@ impl Subword for SubwordStringNonQuoted {
 }
 
 impl SubwordStringNonQuoted {
-    fn new(text: String, pos: DebugInfo/*, is_value: bool*/) -> SubwordStringNonQuoted {
+    pub fn new(text: String, pos: DebugInfo/*, is_value: bool*/) -> SubwordStringNonQuoted {
         SubwordStringNonQuoted {
             text: text.clone(),
             pos: pos,
@@ -48,4 +49,13 @@ impl SubwordStringNonQuoted {
             Some( SubwordStringNonQuoted::new(text.consume(pos), DebugInfo::init(text)/*, false*/) )
         }
     }
+
+    pub fn parse_in_math(text: &mut Feeder) -> Option<SubwordStringNonQuoted> {
+        let pos = text.scanner_math_word();
+        if pos == 0{
+            None
+        }else{
+            Some( SubwordStringNonQuoted::new(text.consume(pos), DebugInfo::init(text)) )
+        }
+    }
 }
@@ -9,13 +9,14 @@ use crate::utils::expand_tilde;
 
 use crate::elements::subword::Subword;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordTildePrefix {
     pub text: String,
     pub pos: DebugInfo,
 }
 
 impl Subword for SubwordTildePrefix {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn get_text(&self) -> String {
         self.text.clone()
     }
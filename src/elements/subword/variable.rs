@@ -8,18 +8,23 @@ use crate::Feeder;
 
 use crate::elements::subword::Subword;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubwordVariable {
     pub text: String,
     pub name: String,
+    pub index: Option<String>,
     pub empty_option: String,
     pub empty_option_string: String,
     pub pos: DebugInfo,
 }
 
 impl Subword for SubwordVariable {
+    fn boxed_clone(&self) -> Box<dyn Subword> { Box::new(self.clone()) }
     fn eval(&mut self, conf: &mut ShellCore, _: bool) -> Vec<Vec<String>> {
-        let val = conf.get_var(&self.name);
+        let val = match &self.index {
+            Some(i) => conf.get_array(&self.name, i),
+            None    => conf.get_var(&self.name),
+        };
 
         if val.len() == 0 {
             vec!(vec!(self.empty_treat(conf)))
@@ -39,6 +44,7 @@ impl SubwordVariable {
     pub fn new(text: &mut Feeder) -> SubwordVariable {
         SubwordVariable {
             name: String::new(),
+            index: None,
             text: String::new(),
             empty_option: String::new(),
             empty_option_string: String::new(),
@@ -92,6 +98,19 @@ impl SubwordVariable {
         ans.name = text.consume(pos);
         ans.text += &ans.name.clone();
 
+        if text.starts_with("[") {
+            let pos = text.scanner_until_escape("]}");
+            if text.chars_after(pos).next() != Some(']') {
+                text.rewind(backup);
+                return None;
+            }
+            ans.text += &text.consume(1);
+            let index = text.consume(pos-1);
+            ans.text += &index.clone();
+            ans.text += &text.consume(1);
+            ans.index = Some(index);
+        }
+
         if text.starts_with( ":-") || text.starts_with( ":=") 
             || text.starts_with( ":?") || text.starts_with( ":+") {
             ans.empty_option = text.consume(2);
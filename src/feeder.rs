@@ -4,7 +4,9 @@
 mod scanner;
 mod term;
 
+use std::cell::RefCell;
 use std::io;
+use std::rc::Rc;
 use std::str::Chars;
 use crate::ShellCore;
 //use crate::term;
@@ -16,6 +18,12 @@ pub struct Feeder {
     from_lineno: u32,
     to_lineno: u32,
     pos_in_line: u32,
+    /* lines read while parsing one command, shared by backups
+     * so that a rewind does not lose them */
+    fed_lines: Rc<RefCell<Vec<String>>>,
+    fed_num: usize,
+    heredoc_lines: u32,
+    from_string: bool, // eval, source, and so on: no more lines are read from the input
 }
 
 impl Feeder {
@@ -25,12 +33,19 @@ impl Feeder {
             from_lineno: 0,
             to_lineno: 0,
             pos_in_line: 0,
+            fed_lines: Rc::new(RefCell::new(vec![])),
+            fed_num: 0,
+            heredoc_lines: 0,
+            from_string: false,
         }
     }
 
     pub fn new_from(text: String) -> Feeder {
         let mut ans = Feeder::new();
         ans.remaining = text;
+        ans.from_lineno = 1;
+        ans.to_lineno = 1;
+        ans.from_string = true;
         ans
     }
 
@@ -74,13 +89,28 @@ impl Feeder {
     pub fn rewind(&mut self, backup: Feeder) {
         self.remaining = backup.remaining.clone();
         self.from_lineno = backup.from_lineno;
-        self.to_lineno = backup.to_lineno;
         self.pos_in_line = backup.pos_in_line;
+        self.heredoc_lines = backup.heredoc_lines;
+
+        if self.remaining.is_empty() && self.fed_num > backup.fed_num {
+            self.from_lineno = backup.to_lineno + 1;
+            self.pos_in_line = 0;
+        }
+        for line in &self.fed_lines.borrow()[backup.fed_num..self.fed_num] {
+            self.remaining += line;
+        }
     }
 
     pub fn consume(&mut self, cutpos: usize) -> String {
         let cut = self.remaining[0..cutpos].to_string();
-        self.pos_in_line += cutpos as u32;
+        match cut.rfind('\n') {
+            Some(n) => {
+                self.from_lineno += cut.matches('\n').count() as u32 + self.heredoc_lines;
+                self.heredoc_lines = 0;
+                self.pos_in_line = (cutpos - n - 1) as u32;
+            },
+            None => self.pos_in_line += cutpos as u32,
+        }
         self.remaining = self.remaining[cutpos..].to_string();
 
         cut
@@ -151,6 +181,9 @@ impl Feeder {
     }
 
     pub fn feed_line(&mut self, core: &mut ShellCore) -> bool {
+        self.fed_lines.borrow_mut().clear();
+        self.fed_num = 0;
+
         //let line = if core.flags.i {
         let line = if core.has_flag('i') {
             let len_prompt = term::prompt_normal(core);
@@ -184,6 +217,8 @@ impl Feeder {
 
     fn add_line(&mut self, line: String) {
         self.to_lineno += 1;
+        self.fed_lines.borrow_mut().push(line.clone());
+        self.fed_num += 1;
 
         if self.remaining.len() == 0 {
             self.from_lineno = self.to_lineno;
@@ -194,6 +229,38 @@ impl Feeder {
         };
     }
 
+    /* Takes the lines of a here-document from the ones after the current line,
+     * reading more lines if needed. */
+    pub fn consume_heredoc(&mut self, delim: &str, strip_tabs: bool, conf: &mut ShellCore) -> String {
+        if ! self.remaining.contains('\n') {
+            self.remaining += "\n";
+        }
+        let start = self.remaining.find('\n').unwrap() + 1;
+        let mut body = String::new();
+
+        loop {
+            while ! self.remaining[start..].contains('\n') {
+                if self.from_string && self.remaining.len() > start {
+                    self.remaining += "\n"; // the last line of the string
+                }else if self.from_string || ! self.feed_additional_line(conf) {
+                    let rest = self.remaining.split_off(start);
+                    eprintln!("bash: warning: here-document delimited by end-of-file (wanted `{}')", delim);
+                    return body + &rest;
+                }
+            }
+
+            let end = start + self.remaining[start..].find('\n').unwrap() + 1;
+            let line: String = self.remaining.drain(start..end).collect();
+            self.heredoc_lines += 1;
+
+            let line = if strip_tabs { line.trim_start_matches('\t') } else { &line };
+            if line.trim_end_matches('\n') == delim {
+                return body;
+            }
+            body += line;
+        }
+    }
+
     pub fn request_next_line(&mut self, conf: &mut ShellCore) -> String {
         let t = self.consume_blank_return();
     
@@ -84,6 +84,33 @@ impl Feeder {
         pos
     }
 
+    pub fn scanner_math_word(&mut self) -> usize {
+        let mut escaped = false;
+        let mut pos = 0;
+        for ch in self.remaining.chars() {
+            if escaped {
+                escaped = false;
+                pos += ch.len_utf8();
+                continue;
+            }
+
+            if ch == '\\' {
+                escaped = true;
+                pos += ch.len_utf8();
+                continue;
+            }
+
+            /* stop at parentheses, quotes, start of expansion*/
+            if "()\"'$".contains(ch) {
+                break;
+            }
+
+            pos += ch.len_utf8();
+        }
+
+        pos
+    }
+
     pub fn scanner_double_quoted_word(&mut self) -> usize {
         let mut escaped = false;
         let mut pos = 0;
@@ -111,6 +138,56 @@ impl Feeder {
         pos
     }
 
+    pub fn scanner_heredoc_word(&mut self) -> usize {
+        let mut escaped = false;
+        let mut pos = 0;
+        for ch in self.remaining.chars() {
+            if escaped {
+                escaped = false;
+                pos += ch.len_utf8();
+                continue;
+            }
+
+            if ch == '\\' {
+                escaped = true;
+                pos += ch.len_utf8();
+                continue;
+            }
+
+            /* stop at $ */
+            if ch == '$' {
+                break;
+            }
+
+            pos += ch.len_utf8();
+        }
+
+        pos
+    }
+
+    /* {name} just before a redirection operator */
+    pub fn scanner_fd_var(&mut self) -> usize {
+        if ! self.starts_with("{") {
+            return 0;
+        }
+
+        let end = self.scanner_until(1, "}");
+        if end == 1 || end >= self.len() {
+            return 0;
+        }
+
+        let name = self.from_to(1, end);
+        if name.starts_with(|c: char| c.is_ascii_digit())
+        || ! name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return 0;
+        }
+
+        match self.chars_after(end+1).next() {
+            Some('<') | Some('>') => end+1,
+            _ => 0,
+        }
+    }
+
     pub fn scanner_redirect(&mut self) -> (usize, Option<RedirectOp> ) {
         if self.starts_with("<<<") {
             return (3, Some(RedirectOp::HereStr));
@@ -118,14 +195,20 @@ impl Feeder {
             return (3, Some(RedirectOp::AndAppend));
         }else if self.starts_with(">>") {
             return (2, Some(RedirectOp::Append));
+        }else if self.starts_with("<<-") {
+            return (3, Some(RedirectOp::HereDocTab));
         }else if self.starts_with("<<") {
             return (2, Some(RedirectOp::HereDoc));
         }else if self.starts_with(">&") {
             return (2, Some(RedirectOp::OutputAnd));
+        }else if self.starts_with("<&") {
+            return (2, Some(RedirectOp::InputAnd));
         }else if self.starts_with("&>") {
             return (2, Some(RedirectOp::AndOutput));
         }else if self.starts_with("<>") {
             return (2, Some(RedirectOp::InOut));
+        }else if self.starts_with(">|") {
+            return (2, Some(RedirectOp::Clobber));
         }else if self.starts_with(">") {
             return (1, Some(RedirectOp::Output));
         }else if self.starts_with("<") {
@@ -5,7 +5,7 @@ pub mod completion;
 
 use std::io;
 use std::env;
-use std::io::{Write, stdout, stdin, Stdout, BufReader};
+use std::io::{Read, Write, stdout, Stdout, BufReader};
 use std::fs::File;
 use std::str::Chars;
 
@@ -14,7 +14,13 @@ use termion::cursor::DetectCursorPos;
 use termion::raw::{IntoRawMode, RawTerminal};
 use termion::input::TermRead;
 
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::unistd;
+
 use crate::ShellCore;
+use crate::core::job::JobStatus;
+use crate::core::proc;
 use completion::*;
 
 use crate::utils::chars_to_string;
@@ -367,11 +373,68 @@ pub fn prompt_normal(core: &mut ShellCore) -> u16 {
     (chars_to_width(&(user + &host + &path).chars().collect()) + 2 + 2) as u16
 }
 
+/* stdin without a buffer. With set -b, it returns WouldBlock when
+ * children change their states while no key is typed. */
+struct KeyInput {
+    notify: bool,
+}
+
+/* false when SIGCHLD interrupts the wait */
+fn wait_key() -> bool {
+    let mut fds = [PollFd::new(0, PollFlags::POLLIN)];
+    matches!(poll(&mut fds, -1), Ok(n) if n > 0)
+}
+
+impl Read for KeyInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.notify && proc::collect_states() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        while self.notify && ! wait_key() {
+            if proc::collect_states() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+        }
+
+        loop {
+            match unistd::read(0, buf) {
+                Err(Errno::EINTR) => continue,
+                res => return res.map_err(|e| e.into()),
+            }
+        }
+    }
+}
+
+/* reports the finished jobs at the prompt for set -b */
+fn notify_jobs(writer: &mut Writer, core: &mut ShellCore) {
+    for j in 1..core.jobs.backgrounds.len() {
+        core.jobs.backgrounds[j].check_of_finish();
+    }
+    if ! core.jobs.backgrounds.iter().any(|j| j.status == JobStatus::Done) {
+        return;
+    }
+
+    write!(writer.stdout, "\r\n").unwrap();
+    writer.stdout.suspend_raw_mode().unwrap();
+    core.check_jobs();
+    writer.stdout.activate_raw_mode().unwrap();
+    prompt_normal(core);
+    let (_, y) = writer.cursor_pos();
+    writer.rewrite_line(y, writer.chars.iter().collect());
+}
+
 pub fn read_line_terminal(left: u16, core: &mut ShellCore) -> Option<String>{
     let mut writer = Writer::new(core.history.len(), left);
     let mut tab_num = 0;
 
-    for c in stdin().keys() {
+    for c in (KeyInput { notify: core.has_flag('b') }).keys() {
+        if let Err(e) = &c {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                notify_jobs(&mut writer, core);
+                continue;
+            }
+        }
+
         match &c.as_ref().unwrap() {
             event::Key::Ctrl('a') => writer.move_cursor_to_head(),
             event::Key::Ctrl('b') => writer.move_cursor(-1),
@@ -7,14 +7,23 @@ use crate::elements::redirect::Redirect;
 use crate::operators::RedirectOp;
 use std::fs::OpenOptions;
 use std::os::unix::io::IntoRawFd;
+use std::os::unix::fs::OpenOptionsExt;
 use crate::ShellCore;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::{Seek, SeekFrom, Write};
+use nix::fcntl::{fcntl, FcntlArg};
+use nix::errno::Errno;
+use std::process;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FileDescs {
     pub redirects: Vec<Box<Redirect>>,
     pub pipein: RawFd,
     pub pipeout: RawFd,
     pub prevpipein: RawFd,
+    pub pipeerr: bool, // |&
 }
 
 impl FileDescs {
@@ -24,6 +33,7 @@ impl FileDescs {
             pipein: -1,
             pipeout: -1,
             prevpipein: -1,
+            pipeerr: false,
         }
     }
 
@@ -51,57 +61,234 @@ impl FileDescs {
                 return Err(s);
             }
         };
+
+        /* |& is 2>&1 performed after the other redirections */
+        if self.pipeerr && self.pipeout != -1 {
+            dup2(1, 2).expect("Cannot duplicate stdout");
+        }
     
         Ok(())
     }
     
-    fn set_redirect(r: &mut Box<Redirect>, conf: &mut ShellCore) -> Result<(), String> {
-        let path = r.eval(conf);
-        if r.redirect_type == RedirectOp::Output /*">"*/ {
-            if let Ok(file) = OpenOptions::new().truncate(true).write(true).create(true).open(&path){
-                Self::dup_and_close(file.into_raw_fd(), r.left_fd);
-            }else{
-                panic!("Cannot open the file: {}", path);
+    /* new files get 0666 masked by the umask of the shell, as open(2) does */
+    fn open_file(path: &str, op: &RedirectOp, noclobber: bool) -> Result<RawFd, String> {
+        let mut options = OpenOptions::new();
+        options.mode(0o666);
+        match op {
+            RedirectOp::Input => options.read(true),
+            RedirectOp::InOut => options.read(true).write(true).create(true),
+            RedirectOp::Append | RedirectOp::AndAppend => options.append(true).create(true),
+            RedirectOp::Output | RedirectOp::AndOutput if noclobber => {
+                /* only regular files are protected */
+                match fs::metadata(path) {
+                    Ok(m) if m.is_file() => return Err(format!("bash: {}: cannot overwrite existing file", path)),
+                    Ok(_) => options.write(true),
+                    Err(_) => options.write(true).create_new(true),
+                }
+            },
+            _ => options.write(true).truncate(true).create(true),
+        };
+
+        match options.open(path) {
+            Ok(file) => Ok(file.into_raw_fd()),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                Err(format!("bash: {}: cannot overwrite existing file", path))
+            },
+            Err(e) => {
+                let msg = Errno::from_i32(e.raw_os_error().unwrap_or(0)).desc();
+                Err(format!("bash: {}: {}", path, msg))
+            },
+        }
+    }
+
+    /* the text goes through an unlinked temporary file so that its size is not
+     * limited by the capacity of a pipe */
+    fn set_here_text(body: &str, fd: RawFd) -> Result<(), String> {
+        let dir = env::var("TMPDIR").unwrap_or("/tmp".to_string());
+        let path = format!("{}/rusty_bash_heredoc_{}", dir, process::id());
+
+        let mut file = match OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(f) => f,
+            Err(e) => return Err(format!("bash: cannot create temp file for here-document: {}", e)),
+        };
+        let _ = fs::remove_file(&path);
+
+        if file.write_all(body.as_bytes()).and_then(|_| file.seek(SeekFrom::Start(0))).is_err() {
+            return Err("bash: cannot write a here-document".to_string());
+        }
+        Self::dup_and_close(file.into_raw_fd(), fd);
+        Ok(())
+    }
+
+    /* n>&m, n<&m, n>&m- (move), n>&- (close), and >&file as &>file */
+    fn set_dup(r: &mut Box<Redirect>, word: &str, noclobber: bool) -> Result<(), String> {
+        if word == "-" {
+            let _ = close(r.left_fd);
+            return Ok(());
+        }
+
+        let (num, moving) = match word.strip_suffix('-') {
+            Some(w) => (w, true),
+            None    => (word, false),
+        };
+
+        match num.parse::<RawFd>() {
+            Ok(n) => {
+                if fcntl(n, FcntlArg::F_GETFD).is_err() {
+                    return Err(format!("bash: {}: Bad file descriptor", num));
+                }
+                if n != r.left_fd {
+                    if dup2(n, r.left_fd).is_err() {
+                        return Err(format!("bash: {}: Bad file descriptor", r.left_fd));
+                    }
+                    if moving {
+                        let _ = close(n);
+                    }
+                }
+                Ok(())
+            },
+            Err(_) if r.redirect_type == RedirectOp::OutputAnd && ! r.left_fd_given
+                    && ! moving && ! word.is_empty() => {
+                let fd = Self::open_file(word, &RedirectOp::AndOutput, noclobber)?;
+                Self::dup_and_close(fd, 1);
+                dup2(1, 2).expect("Redirection error on >&");
+                Ok(())
+            },
+            Err(_) => {
+                match &r.right_word {
+                    Some(a) => Err(format!("bash: {}: ambiguous redirect", a.text.clone())),
+                    None    => Err("Unknown redirect error".to_string()),
+                }
+            },
+        }
+    }
+
+    /* {name}>file and so on: the fd is the lowest free one from 10 */
+    fn set_fd_var(r: &mut Box<Redirect>, conf: &mut ShellCore) -> Result<(), String> {
+        let name = r.fd_var.clone().unwrap_or_default();
+        if r.closes_fd_var() {
+            let value = conf.get_var(&name);
+            return match value.parse::<RawFd>() {
+                Ok(fd) if close(fd).is_ok() => Ok(()),
+                _ => Err(format!("bash: {}: Bad file descriptor", value)),
             };
-        }else if r.redirect_type == RedirectOp::OutputAnd  {
-            if let Ok(n) = path.parse::<i32>() {
-                dup2(n, r.left_fd).expect("Invalid fd");
-            }else{
-                conf.set_var("?", "1");
-                if let Some(a) = &r.right_word {
-                    return Err(format!("bash: {}: ambiguous redirect", a.text.clone()));
-                }else{
-                    return Err("Unknown redirect error".to_string());
+        }
+
+        let mut fd = 10;
+        while fcntl(fd, FcntlArg::F_GETFD).is_ok() {
+            fd += 1;
+        }
+        r.left_fd = fd;
+        conf.set_var(&name, &fd.to_string());
+        Ok(())
+    }
+
+    fn set_redirect(r: &mut Box<Redirect>, conf: &mut ShellCore) -> Result<(), String> {
+        if r.fd_var.is_some() {
+            Self::set_fd_var(r, conf)?;
+            if r.closes_fd_var() {
+                return Ok(());
+            }
+        }
+
+        if r.redirect_type == RedirectOp::HereDoc
+        || r.redirect_type == RedirectOp::HereDocTab {
+            let body = r.eval_here_doc(conf);
+            return Self::set_here_text(&body, r.left_fd);
+        }else if r.redirect_type == RedirectOp::HereStr {
+            let body = r.eval_here_string(conf);
+            return Self::set_here_text(&body, r.left_fd);
+        }
+
+        let path = r.eval(conf)?;
+        let noclobber = conf.has_flag('C');
+        match r.redirect_type {
+            RedirectOp::Output | RedirectOp::Clobber | RedirectOp::Append
+            | RedirectOp::Input | RedirectOp::InOut => {
+                let fd = Self::open_file(&path, &r.redirect_type, noclobber)?;
+                Self::dup_and_close(fd, r.left_fd);
+            },
+            RedirectOp::AndOutput | RedirectOp::AndAppend => {
+                let fd = Self::open_file(&path, &r.redirect_type, noclobber)?;
+                Self::dup_and_close(fd, 1);
+                dup2(1, 2).expect("Redirection error on &> or &>>");
+            },
+            RedirectOp::OutputAnd | RedirectOp::InputAnd => {
+                return Self::set_dup(r, &path, noclobber);
+            },
+            _ => {},
+        }
+    
+        Ok(())
+    }
+
+    /* applies the redirections to the shell itself, keeping copies of
+     * the replaced fds in backups for restore */
+    pub fn set_parent_redirects(&mut self, conf: &mut ShellCore,
+                                backups: &mut Vec<(RawFd, RawFd)>) -> Result<(), String> {
+        let _ = io::stdout().flush();
+        for r in &mut self.redirects {
+            if r.fd_var.is_some() {
+                Self::set_redirect(r, conf)?;
+                if ! r.closes_fd_var() {
+                    backups.push((r.left_fd, -1));
                 }
+                continue;
             }
-        }else if r.redirect_type == RedirectOp::AndOutput {
-            if let Ok(file) = OpenOptions::new().truncate(true).write(true).create(true).open(&path){
-                Self::dup_and_close(file.into_raw_fd(), 1);
-                dup2(1, 2).expect("Redirection error on &>");
-            }else{
-                panic!("Cannot open the file: {}", path);
-            };
-        }else if r.redirect_type == RedirectOp::Input /*"<"*/ {
-            if let Ok(file) = OpenOptions::new().read(true).open(&path){
-                Self::dup_and_close(file.into_raw_fd(), r.left_fd);
-            }else{
-                panic!("Cannot open the file: {}", path);
+
+            let targets = match r.redirect_type {
+                RedirectOp::AndOutput | RedirectOp::AndAppend => vec![1, 2],
+                RedirectOp::OutputAnd if ! r.left_fd_given => vec![1, 2],
+                _ => vec![r.left_fd],
             };
+            for fd in targets {
+                let copy = fcntl(fd, FcntlArg::F_DUPFD_CLOEXEC(10)).unwrap_or(-1);
+                backups.push((fd, copy));
+            }
+            Self::set_redirect(r, conf)?;
         }
-    
         Ok(())
     }
 
+    pub fn restore(backups: &mut Vec<(RawFd, RawFd)>) {
+        let _ = io::stdout().flush();
+        while let Some((fd, copy)) = backups.pop() {
+            if copy == -1 {
+                let _ = close(fd);
+            }else{
+                Self::dup_and_close(copy, fd);
+            }
+        }
+    }
+
+    pub fn keep(backups: &mut Vec<(RawFd, RawFd)>) {
+        for (_, copy) in backups.drain(..) {
+            if copy != -1 {
+                let _ = close(copy);
+            }
+        }
+    }
+
+    pub fn no_pipe(&self) -> bool {
+        self.pipein == -1 && self.pipeout == -1 && self.prevpipein == -1
+    }
+
     pub fn dup_and_close(from: RawFd, to: RawFd){
-        close(to).expect(&("Can't close fd: ".to_owned() + &to.to_string()));
+        if from == to {
+            return;
+        }
+        let _ = close(to);
         dup2(from, to).expect("Can't copy file descriptors");
         close(from).expect(&("Can't close fd: ".to_owned() + &from.to_string()));
     }
     
     
-    pub fn set_parent_io(pout: RawFd) {
+    pub fn set_parent_io(pout: RawFd, pprev: RawFd) {
         if pout >= 0 {
             close(pout).expect("Cannot close outfd");
         };
+        if pprev >= 0 {
+            close(pprev).expect("Cannot close infd");
+        };
     }
 }
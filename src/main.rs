@@ -3,6 +3,7 @@
 
 mod bash_glob;
 mod calculator;
+mod time_format;
 mod elements;
 mod operators;
 
@@ -91,6 +92,7 @@ fn main() {
 
     /* Ignore signals */
     proc::ignore_signals();
+    proc::set_sigchld_handler();
     /*
     unsafe { signal::signal(Signal::SIGINT, SigHandler::SigIgn) }.unwrap();
     unsafe { signal::signal(Signal::SIGTTIN, SigHandler::SigIgn) }.unwrap();
@@ -106,10 +108,11 @@ fn main() {
     if words.len() > 1 {
         if let Ok(file) = OpenOptions::new().read(true).open(&words[1]){
             FileDescs::dup_and_close(file.into_raw_fd(), 0);
+            core.script_name = words[1].clone();
         }
     }
 
-    for f in [ "d", "v", "x" ] {
+    for f in [ "d", "m", "v", "x" ] {
         if words.iter().any(|a| has_option(a, f.to_string())) {
             core.flags += f;
         }
@@ -123,7 +126,11 @@ fn main() {
     core.set_var("BASH", &core.args[0].to_string());
     if is_interactive(pid) {
         core.flags += "i";
+        if ! core.has_flag('m') {
+            core.flags += "m";
+        }
     }
+    core.init_tty();
 
     read_bashrc(&mut core);
     main_loop(&mut core);
@@ -148,6 +155,11 @@ fn main_loop(core: &mut ShellCore) {
                 break;
             }
             e.exec(core);
+            if core.abort_flag {
+                core.abort_flag = false;
+                core.return_flag = false;
+                feeder.consume(feeder.len());
+            }
         }
         core.check_jobs();
     }
@@ -18,15 +18,18 @@ pub enum ControlOperator {
     NoChar,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum RedirectOp {
     Output, /* > */ 
+    Clobber, /* >| */ 
     Input, /* < */
     InOut, /* <> */
     AndOutput, /* &> */ 
     OutputAnd, /* >& */ 
+    InputAnd, /* <& */ 
     Append, /* >> */ 
     HereDoc, /* << */ 
+    HereDocTab, /* <<- */ 
     AndAppend, /* &>> */ 
     HereStr, /* <<< */ 
     NoRedirect, 
@@ -0,0 +1,64 @@
+//SPDX-FileCopyrightText: 2023 Ryuichi Ueda ryuichiueda@gmail.com
+//SPDX-License-Identifier: BSD-3-Clause
+
+use std::time::Duration;
+
+pub const DEFAULT_FORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+pub const POSIX_FORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+
+fn seconds_string(t: Duration, precision: usize, long: bool) -> String {
+    let secs = t.as_secs_f64();
+    if ! long {
+        return format!("{:.*}", precision, secs);
+    }
+
+    let min = (secs / 60.0).floor();
+    format!("{}m{:.*}s", min, precision, secs - min*60.0)
+}
+
+fn percentage(real: Duration, user: Duration, sys: Duration) -> String {
+    if real.is_zero() {
+        return "0.00".to_string();
+    }
+
+    format!("{:.2}", (user + sys).as_secs_f64() / real.as_secs_f64() * 100.0)
+}
+
+/* %[p][l]R, %[p][l]U, %[p][l]S, %P, and %% of TIMEFORMAT */
+pub fn format(fmt: &str, real: Duration, user: Duration, sys: Duration) -> String {
+    let mut ans = String::new();
+    let mut chars = fmt.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            ans.push(ch);
+            continue;
+        }
+
+        let mut precision = 3;
+        if let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+            precision = std::cmp::min(d, 3) as usize;
+            chars.next();
+        }
+
+        let long = chars.peek() == Some(&'l');
+        if long {
+            chars.next();
+        }
+
+        match chars.next() {
+            Some('%') => ans.push('%'),
+            Some('R') => ans += &seconds_string(real, precision, long),
+            Some('U') => ans += &seconds_string(user, precision, long),
+            Some('S') => ans += &seconds_string(sys, precision, long),
+            Some('P') => ans += &percentage(real, user, sys),
+            Some(c)   => {
+                ans.push('%');
+                ans.push(c);
+            },
+            None      => ans.push('%'),
+        }
+    }
+
+    ans
+}
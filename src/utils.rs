@@ -276,6 +276,10 @@ pub fn align_elems_on_term(list: &Vec<String>, width: u32) -> String {
 
 
 pub fn get_fullpath(com: &String) -> String {
+    if com.contains('/') {
+        return com.clone();
+    }
+
     let dirs = if let Ok(p) = env::var("PATH") {
         p.split(':').map(|s| s.to_string()).collect()
     }else{
@@ -289,5 +293,5 @@ pub fn get_fullpath(com: &String) -> String {
         }
     }
 
-    "".to_string()
+    com.clone()
 }
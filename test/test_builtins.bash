@@ -19,6 +19,24 @@ EOF
 res=$($com <<< 'source /tmp/.rusty_bash ; echo $A')
 [ "$res" = "B" ] || err $LINENO
 
+res=$($com << 'EOF'
+set -C
+echo $-
+set -o | grep noclobber
+set -- a b
+echo $# $1
+set -x c
+set +x
+echo $1 $-
+set -k ; echo $?
+EOF
+)
+[ "$res" = "C
+noclobber      	on
+2 a
+c C
+2" ] || err $LINENO
+
 res=$($com <<< 'set a b c ; shift; echo $1')
 [ "$res" = "b" ] || err $LINENO
 
@@ -58,5 +76,81 @@ res=$($com <<< 'eval "echo $(echo a b c);" "echo $(echo a b c)"' )
 [ "$res" = "a b c
 a b c" ] || err $LINENO
 
+res=$($com <<< 'f(){ return 3; echo no ; } ; f ; echo $?')
+[ "$res" = "3" ] || err $LINENO
+
+res=$($com <<< 'f(){ false ; return ; } ; f ; echo $?')
+[ "$res" = "1" ] || err $LINENO
+
+res=$($com <<< 'f(){ if true ; then return 5 ; fi ; echo no ; } ; f ; echo $?')
+[ "$res" = "5" ] || err $LINENO
+
+cat << EOF > /tmp/.rusty_bash
+echo \${FUNCNAME[@]} \${BASH_SOURCE[0]}
+return 4
+echo no
+EOF
+res=$($com <<< 'source /tmp/.rusty_bash ; echo $?')
+[ "$res" = "source main /tmp/.rusty_bash
+4" ] || err $LINENO
+
+res=$($com << 'EOF'
+f(){
+	echo ${FUNCNAME[@]} ${BASH_LINENO[@]}
+	caller 0
+	caller 1
+}
+g(){ f ; }
+g
+caller ; echo $?
+EOF
+)
+[ "$res" = "f g main 6 7 0
+6 g main
+7 main main
+1" ] || err $LINENO
+
+res=$($com << 'EOF'
+exec 3> /tmp/.rusty_bash_exec
+echo a >&3
+exec {fd}>> /tmp/.rusty_bash_exec
+echo b >&$fd
+cat /tmp/.rusty_bash_exec
+EOF
+)
+[ "$res" = "a
+b" ] || err $LINENO
+
+res=$($com << 'EOF'
+(exec echo hi) ; echo after
+exec -a foo bash -c 'echo $0'
+echo no
+EOF
+)
+[ "$res" = "hi
+after
+foo" ] || err $LINENO
+
+res=$($com <<< 'exec -l bash -c "echo \$0"')
+[ "$res" = "-bash" ] || err $LINENO
+
+res=$($com <<< 'exec -c env | wc -l')
+[ "$res" = "0" ] || err $LINENO
+
+res=$($com <<< 'exec nosuchcommand ; echo no' 2>&1 ; echo $?)
+[ "$res" = "bash: exec: nosuchcommand: not found
+127" ] || err $LINENO
+
+mkdir -p /tmp/.rusty_bash_exec_dir
+printf '#!/bin/sh\necho local\n' > /tmp/.rusty_bash_exec_dir/rbfoo
+chmod +x /tmp/.rusty_bash_exec_dir/rbfoo
+res=$($com <<< 'cd /tmp/.rusty_bash_exec_dir ; exec rbfoo' 2>&1)
+[ "$res" = "bash: exec: rbfoo: not found" ] || err $LINENO
+res=$($com <<< 'cd /tmp/.rusty_bash_exec_dir ; exec ./rbfoo' 2>&1)
+[ "$res" = "local" ] || err $LINENO
+rm -rf /tmp/.rusty_bash_exec_dir
+
+res=$($com <<< 'exec grep SigIgn /proc/self/status')
+[ "$res" = "$(grep SigIgn /proc/self/status)" ] || err $LINENO
 
 echo OK $0
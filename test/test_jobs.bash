@@ -37,18 +37,173 @@ echo $res | grep '\[1\].*Running sleep 1 &.*\[2\].*Running sleep 1 &' || err $LI
 ### bg COMMAND ###
 
 res="$($com <<< '(sleep 1 ; killall -SIGSTOP sleep ) & sleep 2 ; fg ')"
-echo $res | grep -F '[2]+ Stopped sleep 2 [2]+ Done sleep 2'  || err $LINENO
+echo $res | grep -F '[2]+ Stopped sleep 2 sleep 2'  || err $LINENO
+echo $res | grep -F 'Done' && err $LINENO
 
 res="$($com <<< 'sleep 1 || sleep 1 & jobs')"
 echo $res | grep -F 'sleep 1 || sleep 1 &'  || err $LINENO
 
 
+### kill COMMAND ###
+
+res=$($com << 'EOF' 2> /dev/null
+sleep 5 | sleep 6 &
+kill %1 ; echo $?
+wait
+kill %1 ; echo $?
+EOF
+)
+[ "$res" = "0
+1" ] || err $LINENO
+
+res=$($com << 'EOF'
+sleep 5 &
+kill -STOP %%
+sleep 0.3
+jobs
+kill -s CONT %+
+kill -n 15 %1
+EOF
+)
+echo $res | grep -F '[1]+ Stopped sleep 5 &' || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+kill -l 9 130 TERM sigint
+kill -FOO 1 ; echo $?
+kill abc ; echo $?
+kill -0 999999999 ; echo $?
+EOF
+)
+[ "$res" = "KILL
+INT
+15
+2
+bash: kill: FOO: invalid signal specification
+1
+bash: kill: abc: arguments must be process or job IDs
+1
+bash: kill: (999999999) - No such process
+1" ] || err $LINENO
+
+### PIPELINE JOB ###
+
+res=$($com << 'EOF' 2> /dev/null
+(sleep 0.5 ; pkill -STOP -x -P $$ sleep) &
+sleep 1.51 | sleep 1.52
+disown %1
+jobs %sleep
+bg %sleep
+wait %sleep ; echo $?
+EOF
+)
+echo $res | grep -F '[2]+ Stopped sleep 1.51 | sleep 1.52 [2]+ Stopped sleep 1.51 | sleep 1.52 [2]+ Running sleep 1.51 | sleep 1.52 0' || err $LINENO
+
+res=$($com << 'EOF' 2> /dev/null
+sleep 1 | sleep 1.1 &
+sleep 0.2
+kill -STOP %1
+sleep 0.2
+jobs
+fg ; echo $?
+EOF
+)
+echo $res | grep -F '[1]+ Stopped sleep 1 | sleep 1.1 & sleep 1 | sleep 1.1 0' || err $LINENO
+
+### NOTIFICATION ###
+
+res=$($com << 'EOF' 2>&1
+sleep 5 &
+(exit 2) &
+sleep 5 &
+kill -9 %1 ; kill %3
+wait %2 ; wait %1 ; wait %3
+EOF
+)
+echo $res | grep -F 'Exit 2 (exit 2) &' || err $LINENO
+echo $res | grep -F 'Killed sleep 5 &' || err $LINENO
+echo $res | grep -F 'Terminated sleep 5 &' || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+set -b
+sleep 5 &
+sleep 0.1 &
+sleep 0.5 ; echo end
+kill %1
+EOF
+)
+echo $res | grep -F '[2]+ Done sleep 0.1 & end' || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+sleep 5 &
+sleep 0.1 &
+sleep 0.5 ; echo end
+kill %1
+EOF
+)
+echo $res | grep -F 'end [2]+ Done sleep 0.1 &' || err $LINENO
+
+### JOB CONTROL ###
+
+res=$($com << 'EOF'
+set -m
+set -o | grep monitor
+sh -c 'echo $$ $(ps -o pgid= -p $$)'
+sh -c 'ps -o pgid= -p $$' | sh -c 'ps -o pgid= -p $$ ; cat'
+set +m
+sh -c 'echo $$ $(ps -o pgid= -p $$)'
+EOF
+)
+echo $res | grep -E '^monitor on ([0-9]+) \1 ([0-9]+) \2 ([0-9]+) ([0-9]+)$' || err $LINENO
+echo $res | grep -E ' ([0-9]+) \1$' && err $LINENO
+
+### JOB SPEC ###
+
+res=$($com << 'EOF' 2>&1
+sleep 1 &
+sleep 2 &
+sleep 3 | cat &
+jobs %sl ; echo $?
+jobs %?3 %- %1
+jobs %4 ; echo $?
+wait %2 ; echo $?
+kill %?3 ; wait %3
+disown %1
+jobs
+EOF
+)
+echo $res | grep -F 'bash: jobs: sl: ambiguous job spec 1 [3]+ Running sleep 3 | cat & [2]- Running sleep 2 & [1] Running sleep 1 & bash: jobs: %4: no such job 1 [2]- Done sleep 2 & 0 [3]+ Terminated sleep 3 | cat &' || err $LINENO
+echo $res | grep -F 'cat & [1]' && err $LINENO
+
+res=$($com << 'EOF' 2>&1
+fg ; echo $?
+bg %1 ; echo $?
+disown -a
+sleep 0.1 &
+fg %sleep ; echo $?
+EOF
+)
+echo $res | grep -E '^bash: fg: current: no such job 1 bash: bg: %1: no such job 1 \[1\] [0-9]+ sleep 0.1 0$' || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+sleep 1 &
+sleep 1 | cat &
+jobs -r
+jobs -l %1 | grep -cE '^\[1\]- [0-9]+ Running'
+jobs -p | wc -l
+jobs -s ; jobs -x ; echo $?
+jobs 1 ; echo $?
+disown $(jobs -p | head -n 1) ; echo $?
+jobs
+EOF
+)
+echo $res | grep -F '[1]- Running sleep 1 & [2]+ Running sleep 1 | cat & 1 2 bash: jobs: -x: invalid option jobs: usage: jobs [-lprs] [jobspec ...] 2 bash: jobs: 1: no such job 1 0 [2]+ Running sleep 1 | cat &' || err $LINENO
+
 ### PRIORITY ###
 
-res="$($com <<< 'sleep 1 & sleep 2 & killall -SIGSTOP sleep ; jobs ; killall -SIGCONT sleep')"
+res="$($com <<< 'sleep 1 & sleep 2 & sleep 0.2 ; killall -SIGSTOP sleep ; jobs ; killall -SIGCONT sleep')"
 echo $res | grep -F '[1]- Stopped sleep 1 & [2]+ Stopped sleep 2 &' || err $LINENO
 
-res="$($com <<< 'sleep 1 & sleep 2 & killall -SIGSTOP sleep ; sleep 3 & jobs ; killall -SIGCONT sleep')"
+res="$($com <<< 'sleep 1 & sleep 2 & sleep 0.2 ; killall -SIGSTOP sleep ; sleep 3 & jobs ; killall -SIGCONT sleep')"
 echo $res | grep -F '[1] Stopped sleep 1 & [2]- Stopped sleep 2 & [3]+ Running sleep' || err $LINENO
 
 echo OK $0
@@ -240,6 +240,36 @@ EOF
  2
   3' ] || err $LINENO
 
+# process substitution
+
+res=$($com << 'EOF'
+diff <(echo a ; echo b) <(printf "a\nb\n") && echo same
+cat <(echo ")") <(echo "a b" | tr a x)
+cat < <(echo redirected)
+echo "<(echo no)"
+cat <(cat <(echo nested))
+EOF
+)
+[ "$res" = "same
+)
+x b
+redirected
+<(echo no)
+nested" ] || err $LINENO
+
+res=$($com << 'EOF'
+echo hello > >(tr h H)
+wait
+f(){ cat $1 $2 ; }
+f <(echo one) <(echo two)
+head -n 1 <(yes)
+EOF
+)
+[ "$res" = "Hello
+one
+two
+y" ] || err $LINENO
+
 # expansion of tilde
 
 res=$($com <<< 'echo ~')
@@ -405,6 +435,208 @@ EOF
 )
 [ "$res" = "2" ] || err $LINENO
 
+res=$($com << 'EOF'
+echo a > /tmp/tmp_x
+echo b >> /tmp/tmp_x
+ls -d / aaaaaaaaaaaaaaaaaaaa &>> /tmp/tmp_x
+wc -l < /tmp/tmp_x
+rm /tmp/tmp_x
+EOF
+)
+[ "$res" = "4" ] || err $LINENO
+
+res=$($com << 'EOF'
+echo abc > /tmp/tmp_x
+cat 0<> /tmp/tmp_x
+rev <> /tmp/tmp_x
+rm /tmp/tmp_x
+EOF
+)
+[ "$res" = "abc
+cba" ] || err $LINENO
+
+res=$( (umask 077 ; $com <<< 'echo a >> /tmp/tmp_x ; echo b <> /tmp/tmp_y') ; ls -l /tmp/tmp_x /tmp/tmp_y | cut -c1-10 ; rm -f /tmp/tmp_x /tmp/tmp_y)
+[ "$res" = "b
+-rw-------
+-rw-------" ] || err $LINENO
+
+res=$($com << 'EOF'
+A=x
+cat << END
+$A $(echo b) $((1+2)) \$A "$A" '$A' $
+END
+cat << 'END'
+$A $(echo b)
+END
+cat << "END"
+$A
+END
+EOF
+)
+[ "$res" = 'x b 3 $A "x" '"'x'"' $
+$A $(echo b)
+$A' ] || err $LINENO
+
+res=$($com << 'EOF'
+cat <<A ; cat <<-B | rev
+1
+A
+		23
+	B
+while read l ; do echo $l$l ; done << END
+a
+b
+END
+echo c
+EOF
+)
+[ "$res" = "1
+32
+aa
+bb
+c" ] || err $LINENO
+
+res=$($com << 'EOF'
+f () {
+	cat << END
+$1
+END
+}
+f a ; f b
+EOF
+)
+[ "$res" = "a
+b" ] || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+eval "cat << E
+x
+E"
+eval "cat << E"
+echo line2
+EOF
+)
+echo "$res" | grep -F 'wanted `E' || err $LINENO
+echo "$res" | grep -x x || err $LINENO
+echo "$res" | grep -x line2 || err $LINENO
+
+res=$($com << 'EOF'
+A="a  b *"
+cat <<< $A
+read x <<< "$A" ; echo "$x"
+read y <<< $(echo c) ; echo $y
+rev <<< "$A"
+EOF
+)
+[ "$res" = "a  b *
+a  b *
+c
+* b  a" ] || err $LINENO
+
+res=$($com << 'EOF'
+echo a 3>&1 1>&2 2>&3 3>&- | wc -c
+echo b >&5
+echo $?
+{ echo c >&3 ; } 3>&1
+echo d 4>&1 >&4- | cat
+ls /aaaaaaaaaaaaaaaaaaaa >& /tmp/tmp_x ; wc -l < /tmp/tmp_x
+rm /tmp/tmp_x
+echo e > /tmp/tmp_x ; read l 3< /tmp/tmp_x 0<&3 ; echo $l
+rm /tmp/tmp_x
+cat 0<&- ; echo $?
+EOF
+)
+[ "$res" = "0
+1
+c
+d
+1
+e
+1" ] || err $LINENO
+
+res=$($com << 'EOF'
+: {fd}> /tmp/tmp_x ; echo $fd
+{v}> /tmp/tmp_x ls /proc/self/fd | grep -c 10
+ls -l /proc/self/fd {w}< /tmp/tmp_x | grep -c tmp_x
+rm /tmp/tmp_x
+EOF
+)
+[ "$res" = "10
+1
+1" ] || err $LINENO
+
+res=$($com << 'EOF'
+cd /tmp 2> /dev/null ; pwd
+echo abc > /tmp/tmp_x ; read x < /tmp/tmp_x ; echo $x
+f () { A=a ; echo in f ; }
+f > /tmp/tmp_x ; echo $A ; cat /tmp/tmp_x
+{ B=b ; } > /dev/null ; echo $B
+while read l ; do C=$l ; done < /tmp/tmp_x ; echo $C
+rm /tmp/tmp_x
+EOF
+)
+[ "$res" = "/tmp
+abc
+a
+in f
+b
+in f" ] || err $LINENO
+
+rm -f /tmp/.rusty_bash_noclobber
+res=$($com << 'EOF' 2>&1
+echo a > /tmp/.rusty_bash_noclobber
+set -C
+echo b > /tmp/.rusty_bash_noclobber ; echo $?
+echo c &> /tmp/.rusty_bash_noclobber
+cat /tmp/.rusty_bash_noclobber
+echo d >| /tmp/.rusty_bash_noclobber
+echo e > /dev/null
+cat /tmp/.rusty_bash_noclobber
+set +o noclobber
+echo f > /tmp/.rusty_bash_noclobber
+cat /tmp/.rusty_bash_noclobber
+EOF
+)
+[ "$res" = "bash: /tmp/.rusty_bash_noclobber: cannot overwrite existing file
+1
+bash: /tmp/.rusty_bash_noclobber: cannot overwrite existing file
+a
+d
+f" ] || err $LINENO
+
+res=$($com << 'EOF' 2>&1
+cat < /tmp/.rusty_bash_nodir/a ; echo $?
+echo a > /tmp/.rusty_bash_nodir/a ; echo $?
+echo a > / ; echo $?
+echo a > $HOGEHOGE ; echo $?
+echo a > "" ; echo $?
+{ echo brace ; } > /tmp/.rusty_bash_nodir/a ; echo $?
+(echo paren) > /tmp/.rusty_bash_nodir/a ; echo $?
+f(){ echo f ; } ; f > /tmp/.rusty_bash_nodir/a ; echo $?
+cd / > /tmp/.rusty_bash_nodir/a ; echo $?
+pwd | grep -c '^/$'
+EOF
+)
+[ "$res" = "bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+bash: /: Is a directory
+1
+bash: \$HOGEHOGE: ambiguous redirect
+1
+bash: : No such file or directory
+1
+bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+bash: /tmp/.rusty_bash_nodir/a: No such file or directory
+1
+0" ] || err $LINENO
+
 res=$($com << 'EOF' 
 ls -d /hogehgoe 2>&1
 EOF
@@ -474,6 +706,26 @@ res=$($com <<< '! echo abc | rev | tr abc def')
 res=$($com <<< '! echo abc | rev | false')
 [ "$?" = "0" ] || err $LINENO
 
+res=$($com << 'EOF' 2> /dev/null
+ls /hogehoge |& sed 's/.*/ERR/'
+{ echo out ; echo err >&2 ; } |& cat | wc -l
+f(){ echo fo ; echo fe >&2 ; }
+f |& grep fe
+(echo pe >&2) |& tr a-z A-Z
+if true ; then echo ie >&2 ; fi |& tr a-z A-Z
+ls /hogehoge 2> /dev/null |& wc -l
+EOF
+)
+[ "$res" = "ERR
+2
+fe
+PE
+IE
+1" ] || err $LINENO
+
+res=$($com <<< 'seq 100000 | head -n 1')
+[ "$res" = "1" ] || err $LINENO
+
 ### JOB ###
 
 res=$($com <<< '(sleep 1; echo a) & echo b')
@@ -581,6 +833,67 @@ res=$($com <<< 'echo $((1+2/3))')
 res=$($com <<< 'echo $((-1+2/3))')
 [ "$res" = "-1" ] || err $LINENO
 
+res=$($com <<< 'echo $(( (1+2)*3 ))')
+[ "$res" = "9" ] || err $LINENO
+
+res=$($com <<< 'echo $((1+$(echo a | wc -l)))')
+[ "$res" = "2" ] || err $LINENO
+
+res=$($com <<< '((1+$(echo a | wc -l)));echo $?')
+[ "$res" = "0" ] || err $LINENO
+
+res=$($com <<< 'A=3 ; echo $(( ${A} * "2" ))')
+[ "$res" = "6" ] || err $LINENO
+
+res=$($com <<< 'echo $(( $(echo) + 1 ))')
+[ "$res" = "1" ] || err $LINENO
+
+res=$($com <<< 'echo $(( -(1) )) $(( 2 * -(3 + 1) ))')
+[ "$res" = "-1 -8" ] || err $LINENO
+
+res=$($com <<< '(( 1 + )) ; echo $?' 2>&1)
+[ "$res" = "bash: 1 +: syntax error: operand expected
+1" ] || err $LINENO
+
+### TIME ###
+
+res=$($com <<< 'time -p true' 2>&1 | head -n 1)
+echo "$res" | grep -E '^real 0\.[0-9]{2}$' || err $LINENO
+
+res=$($com <<< 'TIMEFORMAT="%0R %%"; time seq 3 | cat' 2>&1)
+[ "$res" = "1
+2
+3
+0 %" ] || err $LINENO
+
+res=$($com <<< 'TIMEFORMAT=; time echo a' 2>&1)
+[ "$res" = "a" ] || err $LINENO
+
+res=$($com <<< 'TIMEFORMAT=%0R; time' 2>&1)
+[ "$res" = "0" ] || err $LINENO
+
+res=$($com <<< 'TIMEFORMAT=%0R; time fi ; echo a' 2>&1)
+[ "$res" = "" ] || err $LINENO
+
+### COPROC ###
+
+res=$($com <<< 'coproc cat ; echo hello >&${COPROC[1]} ; head -n 1 < /dev/fd/${COPROC[0]}')
+[ "$res" = "hello" ] || err $LINENO
+
+res=$($com <<< 'coproc UP { head -n 1 | tr a-z A-Z ; } ; echo abc >&${UP[1]} ; head -n 1 < /dev/fd/${UP[0]}')
+echo "$res" | grep -x ABC || err $LINENO
+
+res=$($com <<< 'coproc cat ; echo $COPROC_PID ; jobs')
+echo "$res" | grep -E '^[0-9]+$' || err $LINENO
+echo "$res" | grep -F '[1]+ Running' || err $LINENO
+
+res=$($com <<< 'coproc cat ; ls /proc/self/fd | grep -x -e ${COPROC[0]} -e ${COPROC[1]} ; echo end')
+[ "$res" = "end" ] || err $LINENO
+
+res=$($com <<< 'A=x ; echo ${A[0]} ${A[@]} ; coproc A { cat ; } ; echo ${A[0]}' 2>&1)
+echo "$res" | grep -x 'x x' || err $LINENO
+echo "$res" | grep -E '^[0-9]+$' || err $LINENO
+
 ### MULTILINE INPUT ###
 
 res=$($com << 'EOF'
@@ -672,6 +985,22 @@ EOF
 )
 [ "$res" = "acba" ] || err $LINENO
 
+res=$($com << 'EOF'
+f () {
+cat << END
+level $1
+END
+[ $1 -lt 3 ] && f $(( $1 + 1 ))
+}
+f 1
+echo after
+EOF
+)
+[ "$res" = "level 1
+level 2
+level 3
+after" ] || err $LINENO
+
 res=$($com <<< 'echo $( function hoge () { echo abc | rev ; } ; hoge )') 
 [ "$res" = "cba" ] || err $LINENO
 
@@ -749,6 +1078,55 @@ EOF
 res=$($com <<< 'a(){ echo x; return ; echo b ; } ; a')
 [ "$res" = "x" ] || err $LINENO
 
+res=$($com <<< 'function f { echo a ; } ; function g() ( echo b ) ; f ; g')
+[ "$res" = "a
+b" ] || err $LINENO
+
+res=$($com << 'EOF'
+function f
+{
+	echo a
+}
+g () if true ; then echo b ; fi
+f ; g
+EOF
+)
+[ "$res" = "a
+b" ] || err $LINENO
+
+res=$($com <<< 'f() { echo $1 ; } > /tmp/.rusty_bash_func ; f a ; f b ; cat /tmp/.rusty_bash_func')
+[ "$res" = "b" ] || err $LINENO
+
+res=$($com << 'EOF'
+f(){ echo $1 ; f $(( $1 + 1 )) ; echo no ; }
+FUNCNEST=3 ; f 1 ; echo no
+echo $?
+EOF
+)
+[ "$res" = "1
+2
+3
+1" ] || err $LINENO
+
+res=$($com <<< 'f(){ f ; } ; f
+echo $?')
+[ "$res" = "1" ] || err $LINENO
+
+res=$($com <<< 'f(){ [ $1 -gt 0 ] && f $(( $1 - 1 )) ; echo $1 ; } ; f 3')
+[ "$res" = "0
+1
+2
+3" ] || err $LINENO
+
+res=$($com <<< 'i=0 ; while [ $i -lt 2 ] ; do f(){ echo $i ; } ; i=$(( $i + 1 )) ; done ; f ; f')
+[ "$res" = "2
+2" ] || err $LINENO
+
+res=$($com <<< 'f(){ echo $1 ; } ; f ab | rev ; f ab ; f cd | rev')
+[ "$res" = "ba
+ab
+dc" ] || err $LINENO
+
 ### IF COMPOUND ###
 
 res=$($com <<< 'if [ "a" == "a" ] ; then echo aa; fi')
@@ -959,6 +1337,38 @@ EOF
 )
 [ "$res" = "no" ] || err $LINENO
 
+res=$($com <<< 'A=abc ; case abcdef in "$A"*) echo x ;; *) echo no ;; esac')
+[ "$res" = "x" ] || err $LINENO
+
+res=$($com <<< "case abc in '*') echo x ;; *) echo no ;; esac")
+[ "$res" = "no" ] || err $LINENO
+
+res=$($com <<< 'A="a*" ; case abc in "$A") echo x ;; $A) echo y ;; esac')
+[ "$res" = "y" ] || err $LINENO
+
+res=$($com <<< 'shopt -s nocasematch ; case ABC in abc) echo x ;; esac')
+[ "$res" = "x" ] || err $LINENO
+
+res=$($com <<< 'case a in a) echo 1 ;& b) echo 2 ;& c) echo 3 ;; d) echo 4 ;; esac')
+[ "$res" = "1
+2
+3" ] || err $LINENO
+
+res=$($com <<< 'case ab in a*) echo 1 ;;& c*) echo 2 ;;& *b) echo 3 ;; *) echo 4 ;; esac')
+[ "$res" = "1
+3" ] || err $LINENO
+
+res=$($com << 'EOF'
+case a in
+	(a|b) echo x ;&
+	(c)
+	;;
+	*) echo no ;;
+esac
+EOF
+)
+[ "$res" = "x" ] || err $LINENO
+
 cat << EOF > $tmp 
 echo hoge
 EOF